@@ -0,0 +1,254 @@
+use std::collections::HashSet;
+
+use anyhow::Result;
+use geo::{Distance, Euclidean, Point};
+use geojson::FeatureCollection;
+use graph::RoadID;
+use serde::{Deserialize, Serialize};
+
+use crate::{
+    od::SlowStats, stats::percent, utils::into_object_value, Dir, LevelOfService, MapModel,
+};
+
+/// One score from 0 (worst) to 1 (best) per core principle of Cycling by Design. The formulas are
+/// simple and meant for comparing options, not as an audit.
+#[derive(Default, Serialize, Deserialize)]
+pub struct CoherenceScores {
+    /// 80% from the length-weighted inverse of the LoS penalty (so high LoS is 1 and "should not
+    /// be used" is 0.2), 20% from the share of movements through major junctions at high LoS
+    pub safety: f64,
+    /// Network-wide, half from the inverse of the number of disconnected components, half from
+    /// the share of population reachable. Per route, the share of its two ends connected to
+    /// another route.
+    pub coherence: f64,
+    /// Network-wide, the inverse of the average weighted directness of OD routes, only when that
+    /// has been calculated. Per route, the straight-line distance between its ends divided by its
+    /// length.
+    pub directness: Option<f64>,
+    /// Half from the share of length with a gradient of at most 3%, half from the share of length
+    /// where the infrastructure type fits in the available street space
+    pub comfort: f64,
+    /// The share of length that's attractive (near greenspace, for example)
+    pub attractiveness: f64,
+    /// The mean of all the other scores
+    pub overall: f64,
+}
+
+impl CoherenceScores {
+    fn new(
+        safety: f64,
+        coherence: f64,
+        directness: Option<f64>,
+        comfort: f64,
+        attractiveness: f64,
+    ) -> Self {
+        let mut scores = vec![safety, coherence, comfort, attractiveness];
+        scores.extend(directness);
+        let overall = scores.iter().sum::<f64>() / scores.len() as f64;
+        Self {
+            safety,
+            coherence,
+            directness,
+            comfort,
+            attractiveness,
+            overall,
+        }
+    }
+}
+
+/// Length-weighted scores over some roads
+struct RoadShares {
+    los: f64,
+    comfort: f64,
+    attractiveness: f64,
+}
+
+impl MapModel {
+    /// Scores for the whole network. `slow_stats` are needed for directness.
+    pub fn get_coherence_scores(&self, slow_stats: Option<&SlowStats>) -> CoherenceScores {
+        let network: Vec<RoadID> = (0..self.graph.roads.len())
+            .map(RoadID)
+            .filter(|r| self.infra_types[r.0].is_some())
+            .collect();
+        let shares = self.road_shares(&network);
+
+        let (high_movements, total_movements) = self.count_junction_movements();
+        let safety = 0.8 * shares.los + 0.2 * junction_share(high_movements, total_movements);
+
+        let num_components = self.num_connected_components();
+        let reachable = self.get_reachable_network();
+        let mut population_sum = 0;
+        let mut population_total = 0;
+        for zone in &self.data_zones {
+            population_total += zone.population;
+            if reachable.covers_any(&zone.roads) {
+                population_sum += zone.population;
+            }
+        }
+        let coherence = if num_components == 0 {
+            0.0
+        } else {
+            0.5 / num_components as f64 + 0.5 * percent(population_sum, population_total)
+        };
+
+        let directness = slow_stats.map(|x| directness_score(x.average_weighted_directness));
+
+        CoherenceScores::new(
+            safety,
+            coherence,
+            directness,
+            shares.comfort,
+            shares.attractiveness,
+        )
+    }
+
+    /// A FeatureCollection of every route, with `CoherenceScores` as properties
+    pub fn get_route_coherence_scores(&self) -> Result<FeatureCollection> {
+        let mut features = Vec::new();
+        for (id, route) in &self.routes {
+            let roads: Vec<RoadID> = route.roads.iter().map(|(r, _)| *r).collect();
+            let shares = self.road_shares(&roads);
+
+            let movements = self.assess_route_junctions(*id)?;
+            let safety = 0.8 * shares.los
+                + 0.2
+                    * junction_share(
+                        movements
+                            .iter()
+                            .filter(|m| m.los == LevelOfService::High)
+                            .count(),
+                        movements.len(),
+                    );
+
+            // Does each end of the route connect to some other part of the network?
+            let other_roads: HashSet<RoadID> = self
+                .routes
+                .iter()
+                .filter(|(other_id, _)| *other_id != id)
+                .flat_map(|(_, other)| other.roads.iter().map(|(r, _)| *r))
+                .collect();
+            let (first, first_dir) = route.roads[0];
+            let start_i = match first_dir {
+                Dir::Forwards => self.graph.roads[first.0].src_i,
+                Dir::Backwards => self.graph.roads[first.0].dst_i,
+            };
+            let (last, last_dir) = route.roads[route.roads.len() - 1];
+            let end_i = match last_dir {
+                Dir::Forwards => self.graph.roads[last.0].dst_i,
+                Dir::Backwards => self.graph.roads[last.0].src_i,
+            };
+            let connected_ends = [start_i, end_i]
+                .into_iter()
+                .filter(|i| {
+                    self.graph.intersections[i.0]
+                        .roads
+                        .iter()
+                        .any(|r| other_roads.contains(r))
+                })
+                .count();
+            let coherence = connected_ends as f64 / 2.0;
+
+            let length: f64 = roads
+                .iter()
+                .map(|r| self.graph.roads[r.0].length_meters)
+                .sum();
+            let straight_line = Euclidean.distance(
+                Point::from(self.graph.intersections[start_i.0].point),
+                Point::from(self.graph.intersections[end_i.0].point),
+            );
+            let directness = if length > 0.0 {
+                Some((straight_line / length).min(1.0))
+            } else {
+                None
+            };
+
+            let scores = CoherenceScores::new(
+                safety,
+                coherence,
+                directness,
+                shares.comfort,
+                shares.attractiveness,
+            );
+            let mut f = route.to_gj(*id);
+            for (key, value) in into_object_value(serde_json::to_value(&scores)?) {
+                f.set_property(key, value);
+            }
+            features.push(f);
+        }
+
+        Ok(FeatureCollection {
+            features,
+            bbox: None,
+            foreign_members: None,
+        })
+    }
+
+    fn road_shares(&self, roads: &[RoadID]) -> RoadShares {
+        let mut total = 0.0;
+        let mut los = 0.0;
+        let mut low_gradient = 0.0;
+        let mut fits = 0.0;
+        let mut attractive = 0.0;
+        for r in roads {
+            let length = self.graph.roads[r.0].length_meters;
+            total += length;
+            los += length / self.los_penalty(self.los[r.0]);
+            if self.gradients[r.0].abs() <= 3.0 {
+                low_gradient += length;
+            }
+            if self.infra_types[r.0]
+                .is_some_and(|infra_type| self.does_infra_type_fit(*r, infra_type))
+            {
+                fits += length;
+            }
+            if self.is_attractive[r.0] {
+                attractive += length;
+            }
+        }
+
+        if total == 0.0 {
+            return RoadShares {
+                los: 0.0,
+                comfort: 0.0,
+                attractiveness: 0.0,
+            };
+        }
+        RoadShares {
+            los: los / total,
+            comfort: 0.5 * low_gradient / total + 0.5 * fits / total,
+            attractiveness: attractive / total,
+        }
+    }
+}
+
+/// With no major junctions, there's nothing unsafe to cross
+fn junction_share(high: usize, total: usize) -> f64 {
+    if total == 0 {
+        1.0
+    } else {
+        high as f64 / total as f64
+    }
+}
+
+/// Directness is at least 1, with 1 being a perfectly direct route
+fn directness_score(directness: f64) -> f64 {
+    if directness <= 0.0 {
+        0.0
+    } else {
+        (1.0 / directness).min(1.0)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_overall() {
+        let scores = CoherenceScores::new(1.0, 0.5, None, 0.5, 0.0);
+        assert_eq!(scores.overall, 0.5);
+
+        let scores = CoherenceScores::new(1.0, 0.5, Some(directness_score(2.0)), 0.5, 0.0);
+        assert_eq!(scores.overall, 0.5);
+    }
+}
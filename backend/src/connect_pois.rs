@@ -0,0 +1,222 @@
+use std::collections::{BinaryHeap, HashMap, HashSet};
+
+use anyhow::Result;
+use geo::{Haversine, Length};
+use geojson::{Feature, FeatureCollection, Geometry};
+use graph::RoadID;
+use utils::PriorityQueueItem;
+
+use crate::reachable::{meters, roads_to_steps, route_input_from_roads};
+use crate::routes::glue_route_wgs84;
+use crate::{utils::into_object_value, Dir, MapModel, SetRouteInput, Tier, Waypoint};
+
+/// One unreachable POI, identified the same way as the web UI does
+struct Terminal {
+    kind: String,
+    idx: usize,
+    roads: HashSet<RoadID>,
+}
+
+/// A proposed route connecting one or more POIs to the network or to another connection
+struct Connection {
+    roads: Vec<(RoadID, Dir)>,
+    /// (kind, index) of each POI reached
+    pois: Vec<(String, usize)>,
+}
+
+impl MapModel {
+    /// Like `fix_unreachable_poi`, but for every unreachable POI of some kinds at once.
+    /// Connections are built greedily, always connecting the POI closest to the network or a
+    /// previous connection, so nearby POIs share roads. This approximates a Steiner tree.
+    ///
+    /// Returns a stringified FeatureCollection<LineString, SetRouteInput & { length_meters, pois:
+    /// { poi_kind, poi_idx }[] }>. If `apply` is true, all of the routes are also created, or none of them are if
+    /// something fails.
+    pub fn fix_all_unreachable_pois(&mut self, kinds: Vec<String>, apply: bool) -> Result<String> {
+        let reachable = self.get_reachable_network();
+        let mut tree: HashSet<RoadID> = (0..self.graph.roads.len())
+            .map(RoadID)
+            .filter(|r| self.infra_types[r.0].is_some())
+            .collect();
+
+        let mut terminals = Vec::new();
+        // These are touching the network already, but it's not high LoS. Adding connections
+        // won't help.
+        let mut num_low_los = 0;
+        for kind in kinds {
+            for (idx, roads) in self.get_pois_of_kind(&kind)?.into_iter().enumerate() {
+                if reachable.covers_any(&roads) {
+                    continue;
+                }
+                if !tree.is_disjoint(&roads) {
+                    num_low_los += 1;
+                    continue;
+                }
+                terminals.push(Terminal {
+                    kind: kind.clone(),
+                    idx,
+                    roads,
+                });
+            }
+        }
+        info!("Connecting {} unreachable POIs", terminals.len());
+
+        let mut connections = Vec::new();
+        while !terminals.is_empty() {
+            let Some(roads) = self.path_to_closest_terminal(&tree, &terminals) else {
+                break;
+            };
+            tree.extend(roads.iter().cloned());
+
+            // This connection might reach several POIs at once
+            let mut pois = Vec::new();
+            terminals.retain(|t| {
+                if tree.is_disjoint(&t.roads) {
+                    true
+                } else {
+                    pois.push((t.kind.clone(), t.idx));
+                    false
+                }
+            });
+            connections.push(Connection {
+                roads: roads_to_steps(&self.graph, roads)?,
+                pois,
+            });
+        }
+        // Anything left is disconnected from the network entirely
+        let num_failed = terminals.len();
+
+        let mut features = Vec::new();
+        let mut inputs = Vec::new();
+        let mut total_length_meters = 0.0;
+        for connection in &connections {
+            let linestring_wgs84 = glue_route_wgs84(&self.graph, &connection.roads);
+            let input = route_input_from_roads(
+                &self.graph,
+                &connection.roads,
+                "connection to local POI".to_string(),
+                String::new(),
+                Tier::LocalAccess,
+            );
+            let length = Haversine.length(&linestring_wgs84);
+            total_length_meters += length;
+
+            let mut f = Feature::from(Geometry::from(&linestring_wgs84));
+            f.properties = Some(into_object_value(serde_json::to_value(&input)?));
+            f.set_property("length_meters", length);
+            f.set_property(
+                "pois",
+                connection
+                    .pois
+                    .iter()
+                    .map(|(kind, idx)| serde_json::json!({ "poi_kind": kind, "poi_idx": idx }))
+                    .collect::<Vec<_>>(),
+            );
+            features.push(f);
+            inputs.push(input);
+        }
+
+        if apply {
+            self.apply_all_routes(inputs)?;
+        }
+
+        Ok(serde_json::to_string(&FeatureCollection {
+            features,
+            bbox: None,
+            foreign_members: Some(into_object_value(serde_json::json!({
+                "total_length_meters": total_length_meters,
+                "num_connected": connections.iter().map(|c| c.pois.len()).sum::<usize>(),
+                "num_failed": num_failed,
+                "num_low_los": num_low_los,
+            }))),
+        })?)
+    }
+
+    /// Creates many routes from WGS84 inputs. If any fail, none are created.
+    pub fn apply_all_routes(&mut self, inputs: Vec<SetRouteInput>) -> Result<()> {
+        let orig_routes = self.routes.clone();
+        let orig_id_counter = self.id_counter;
+
+        for mut input in inputs {
+            input.waypoints = input
+                .waypoints
+                .into_iter()
+                .map(|w| Waypoint {
+                    point: self.graph.mercator.pt_to_mercator(w.point.into()).into(),
+                    snapped: w.snapped,
+                })
+                .collect();
+            if let Err(err) = self.set_route(None, input) {
+                self.routes = orig_routes;
+                self.id_counter = orig_id_counter;
+                self.recalculate_after_edits();
+                return Err(err);
+            }
+        }
+        Ok(())
+    }
+
+    /// Search from everything in the tree to the closest road of any terminal, ignoring LoS.
+    /// Returns the path in order from the terminal to the tree, excluding the road in the tree.
+    fn path_to_closest_terminal(
+        &self,
+        tree: &HashSet<RoadID>,
+        terminals: &[Terminal],
+    ) -> Option<Vec<RoadID>> {
+        let goals: HashSet<RoadID> = terminals
+            .iter()
+            .flat_map(|t| t.roads.iter().cloned())
+            .collect();
+
+        let mut backrefs: HashMap<RoadID, RoadID> = HashMap::new();
+        // The cheapest cost pushed so far for each road. Backrefs are only set when this improves,
+        // so they always describe the path that was costed.
+        let mut best: HashMap<RoadID, usize> = HashMap::new();
+        let mut queue: BinaryHeap<PriorityQueueItem<usize, RoadID>> = BinaryHeap::new();
+        for r in tree {
+            best.insert(*r, 0);
+            queue.push(PriorityQueueItem::new(0, *r));
+        }
+
+        while let Some(item) = queue.pop() {
+            let r1 = item.value;
+            // A cheaper path was pushed later
+            if best.get(&r1).is_some_and(|cost| *cost < item.cost) {
+                continue;
+            }
+
+            if goals.contains(&r1) {
+                let mut roads_in_order = vec![r1];
+                let mut current = r1;
+                while let Some(next) = backrefs.get(&current) {
+                    if tree.contains(next) {
+                        break;
+                    }
+                    roads_in_order.push(*next);
+                    current = *next;
+                }
+                return Some(roads_in_order);
+            }
+
+            // Moving along the tree is free
+            let road1 = &self.graph.roads[r1.0];
+            let cost = if tree.contains(&r1) {
+                item.cost
+            } else {
+                item.cost + meters(road1.length_meters)
+            };
+            for i in [road1.src_i, road1.dst_i] {
+                for r2 in &self.graph.intersections[i.0].roads {
+                    if tree.contains(r2) || best.get(r2).is_some_and(|x| *x <= cost) {
+                        continue;
+                    }
+                    best.insert(*r2, cost);
+                    backrefs.insert(*r2, r1);
+                    queue.push(PriorityQueueItem::new(cost, *r2));
+                }
+            }
+        }
+
+        None
+    }
+}
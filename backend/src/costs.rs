@@ -1,8 +1,12 @@
 use std::time::Duration;
 
-use graph::{Road, Timer};
+use graph::{Road, RoadID, Timer};
 
-use crate::{LevelOfService, MapModel};
+use crate::{Dir, LevelOfService, MapModel};
+
+/// Crossing a road at a junction costs as much as riding this far, scaled by how much worse the
+/// crossed road's LoS is than high. Made up.
+const JUNCTION_METERS: f64 = 50.0;
 
 impl MapModel {
     /// After some kind of edit, recalculate edge costs for bicycle_quiet.
@@ -16,7 +20,11 @@ impl MapModel {
 
         let mut costs = Vec::new();
         for (idx, road) in self.graph.roads.iter().enumerate() {
-            costs.push(quiet_edge_cost(road, self.los[idx]));
+            costs.push(quiet_edge_cost(
+                road,
+                self.los_penalty(self.los[idx]),
+                self.approx_junction_cost_meters(RoadID(idx)),
+            ));
         }
         for (road, cost) in self.graph.roads.iter_mut().zip(costs.into_iter()) {
             road.cost = vec![cost];
@@ -26,9 +34,91 @@ impl MapModel {
         let profile = self.graph.profile_names["bicycle_quiet"];
         self.graph.routers[profile.0].update_costs(&self.graph.roads, profile);
     }
+
+    /// An extra cost, in meters, for moving between two roads through a junction and crossing
+    /// roads with motor traffic. Crossings follow the same rules as reachability, so a planned
+    /// crossing, or infrastructure on both roads, removes the cost.
+    pub(crate) fn junction_cost_meters(&self, (r1, dir1): (RoadID, Dir), to: (RoadID, Dir)) -> f64 {
+        let road1 = &self.graph.roads[r1.0];
+        let i = if dir1 == Dir::Forwards {
+            road1.dst_i
+        } else {
+            road1.src_i
+        };
+        crossing_cost_meters(
+            self.unprotected_crossed_roads(i, r1, to.0, self.has_crossing(i)),
+            |r| self.los_penalty(self.los[r.0]) - self.los_penalty(LevelOfService::High),
+        )
+    }
+
+    /// The router only prices roads, not movements between them, so this is a known
+    /// approximation: add half the average junction cost of leaving each end of the road, over
+    /// every possible next road. Routes avoid roads leading into junctions that are hard to cross,
+    /// but an easy turn out of such a junction is still charged as if it might go straight across.
+    pub(crate) fn approx_junction_cost_meters(&self, r: RoadID) -> f64 {
+        let road = &self.graph.roads[r.0];
+        let mut total = 0.0;
+        for (i, dir) in [(road.dst_i, Dir::Forwards), (road.src_i, Dir::Backwards)] {
+            let others: Vec<RoadID> = self.graph.intersections[i.0]
+                .roads
+                .iter()
+                .cloned()
+                .filter(|other| *other != r)
+                .collect();
+            if others.is_empty() {
+                continue;
+            }
+            // The direction of the next road doesn't matter
+            let sum: f64 = others
+                .iter()
+                .map(|other| self.junction_cost_meters((r, dir), (*other, Dir::Forwards)))
+                .sum();
+            total += 0.5 * sum / (others.len() as f64);
+        }
+        total
+    }
 }
 
-fn quiet_edge_cost(road: &Road, los: LevelOfService) -> Duration {
+fn quiet_edge_cost(road: &Road, penalty: f64, junction_meters: f64) -> Duration {
     // TODO Ignore cyclist speed for now. Later, do include it -- slower on SharedFootway or uphill
-    Duration::from_secs_f64(los.penalty() * road.length_meters)
+    Duration::from_secs_f64(penalty * road.length_meters + junction_meters)
+}
+
+/// The cost of crossing some roads in one movement. Crossing several arms of the same junction
+/// happens in one go, so only the worst counts.
+fn crossing_cost_meters<F: Fn(RoadID) -> f64>(crossed: Vec<RoadID>, extra_penalty: F) -> f64 {
+    crossed
+        .into_iter()
+        .map(|r| extra_penalty(r).max(0.0))
+        .fold(0.0, f64::max)
+        * JUNCTION_METERS
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::reachable::all_crossed_roads;
+
+    #[test]
+    fn test_crossing_cost() {
+        // A quiet street crossing an arterial road, with the arms listed clockwise
+        let (north, east, south, west) = (RoadID(0), RoadID(1), RoadID(2), RoadID(3));
+        let clockwise = vec![north, east, south, west];
+        let extra_penalty = |r: RoadID| {
+            if r == east || r == west {
+                LevelOfService::Low.penalty() - LevelOfService::High.penalty()
+            } else {
+                0.0
+            }
+        };
+        let cost =
+            |r1, r2| crossing_cost_meters(all_crossed_roads(&clockwise, r1, r2), extra_penalty);
+
+        let straight_across = cost(north, south);
+        assert!(straight_across > 0.0);
+        // Turning left onto the arterial road (in the UK) crosses nothing
+        assert_eq!(cost(north, east), 0.0);
+        // Staying on the arterial road only crosses the quiet street
+        assert_eq!(cost(east, west), 0.0);
+    }
 }
@@ -0,0 +1,161 @@
+use std::collections::HashMap;
+
+use anyhow::Result;
+use enum_map::Enum;
+use geo::{Distance, Euclidean, Point};
+use geojson::FeatureCollection;
+use graph::IntersectionID;
+use serde::{Deserialize, Serialize};
+
+use crate::MapModel;
+
+/// A planned crossing at a junction, letting cyclists cross roads that aren't high LoS
+#[derive(
+    Clone, Copy, Debug, PartialEq, Eq, PartialOrd, Ord, Hash, Enum, Serialize, Deserialize,
+)]
+pub enum CrossingKind {
+    Zebra,
+    Parallel,
+    Signalised,
+    GradeSeparated,
+}
+
+impl CrossingKind {
+    /// A rough capital cost in pounds. These are placeholder values for comparing options, not
+    /// estimates for a real scheme.
+    pub fn cost(self) -> f64 {
+        match self {
+            CrossingKind::Zebra => 60_000.0,
+            CrossingKind::Parallel => 90_000.0,
+            CrossingKind::Signalised => 250_000.0,
+            CrossingKind::GradeSeparated => 3_000_000.0,
+        }
+    }
+}
+
+/// Crossings further than this from a junction (in meters) aren't snapped to it
+const CROSSING_SNAP_METERS: f64 = 10.0;
+
+/// How crossings are stored in the savefile. Intersection IDs aren't stable when the model is
+/// regenerated, so use the position.
+#[derive(Serialize, Deserialize)]
+pub struct SavedCrossing {
+    // WGS84
+    point: [f64; 2],
+    kind: CrossingKind,
+}
+
+impl MapModel {
+    /// Adds, changes, or removes (with None) a crossing at one junction
+    pub fn set_crossing(&mut self, i: IntersectionID, kind: Option<CrossingKind>) -> Result<()> {
+        if self.graph.intersections[i.0].roads.len() < 4 {
+            bail!("Junctions with fewer than 4 arms don't need crossings");
+        }
+        match kind {
+            Some(kind) => {
+                self.crossings.insert(i, kind);
+            }
+            None => {
+                self.crossings.remove(&i);
+            }
+        }
+        // Crossings change junction costs
+        self.quiet_router_ok = false;
+        Ok(())
+    }
+
+    /// Returns GJ points for every crossing
+    pub fn get_crossings(&self) -> FeatureCollection {
+        let mut features = Vec::new();
+        for (i, kind) in &self.crossings {
+            let mut f = self
+                .graph
+                .mercator
+                .to_wgs84_gj(&self.graph.intersections[i.0].point);
+            f.set_property("intersection", i.0);
+            f.set_property("kind", serde_json::to_value(kind).unwrap());
+            f.set_property("cost", kind.cost());
+            features.push(f);
+        }
+        FeatureCollection {
+            features,
+            bbox: None,
+            foreign_members: None,
+        }
+    }
+
+    pub fn has_crossing(&self, i: IntersectionID) -> bool {
+        self.crossings.contains_key(&i)
+    }
+
+    pub fn save_crossings(&self) -> Vec<SavedCrossing> {
+        self.crossings_to_saved(&self.crossings)
+    }
+
+    pub fn load_crossings(&mut self, saved: Vec<SavedCrossing>) {
+        self.crossings = self.snap_saved_crossings(saved);
+        self.quiet_router_ok = false;
+    }
+
+    pub(crate) fn crossings_to_saved(
+        &self,
+        crossings: &HashMap<IntersectionID, CrossingKind>,
+    ) -> Vec<SavedCrossing> {
+        crossings
+            .iter()
+            .map(|(i, kind)| {
+                let pt = self
+                    .graph
+                    .mercator
+                    .to_wgs84(&self.graph.intersections[i.0].point);
+                SavedCrossing {
+                    point: [pt.x(), pt.y()],
+                    kind: *kind,
+                }
+            })
+            .collect()
+    }
+
+    /// Snaps saved crossings to junctions. If the map has changed since the crossings were saved,
+    /// some might not be near a suitable junction anymore; these are dropped with a warning.
+    pub(crate) fn snap_saved_crossings(
+        &self,
+        saved: Vec<SavedCrossing>,
+    ) -> HashMap<IntersectionID, CrossingKind> {
+        let mut crossings = HashMap::new();
+        for crossing in saved {
+            let pt: Point = self
+                .graph
+                .mercator
+                .pt_to_mercator(crossing.point.into())
+                .into();
+            match self.snap_to_crossing_junction(pt) {
+                Ok(i) => {
+                    crossings.insert(i, crossing.kind);
+                }
+                Err(err) => {
+                    warn!(
+                        "Dropping {:?} crossing at {:?}: {err}",
+                        crossing.kind, crossing.point
+                    );
+                }
+            }
+        }
+        crossings
+    }
+
+    /// Finds the junction a crossing at this point (Mercator) belongs to. Fails if there's no
+    /// junction nearby, or the closest one has fewer than 4 arms.
+    pub fn snap_to_crossing_junction(&self, pt: Point) -> Result<IntersectionID> {
+        let i = self.snap_to_intersection(pt, None);
+        let intersection = &self.graph.intersections[i.0];
+        let dist = Euclidean.distance(pt, intersection.point);
+        if dist > CROSSING_SNAP_METERS {
+            bail!("The closest junction is {dist:.0}m away");
+        }
+        if intersection.roads.len() < 4 {
+            bail!("Junctions with fewer than 4 arms don't need crossings");
+        }
+        Ok(i)
+    }
+}
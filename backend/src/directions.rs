@@ -0,0 +1,214 @@
+use geo::{Bearing, Coord, Haversine, Point};
+use graph::{PathStep, RoadID, Route};
+use serde::Serialize;
+
+use crate::{InfraType, LevelOfService, MapModel};
+
+/// One instruction in turn-by-turn directions, covering consecutive roads with the same name, or
+/// consecutive unnamed roads without a turn between them
+#[derive(Serialize)]
+pub struct Instruction {
+    pub name: Option<String>,
+    pub length: f64,
+    /// The OSM way of the first road
+    pub way: String,
+    /// The InfraType covering the most length
+    pub infra_type: InfraType,
+    /// The worst LoS along the instruction
+    pub los: LevelOfService,
+    /// How to get onto this instruction from the previous one
+    pub turn: Turn,
+    /// Degrees, positive for right turns and negative for left turns
+    pub turn_angle: f64,
+    /// Somewhere along this instruction (including the turn onto it), the route crosses a road
+    /// without a high LoS and without a crossing, using the same rules as reachability
+    pub crosses_non_high_los: bool,
+    /// Somewhere along this instruction (including the turn onto it), the route goes from a road
+    /// on the network to one that isn't
+    pub leaves_network: bool,
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Serialize)]
+pub enum Turn {
+    Start,
+    Straight,
+    SlightLeft,
+    Left,
+    SharpLeft,
+    SlightRight,
+    Right,
+    SharpRight,
+    UTurn,
+}
+
+impl MapModel {
+    pub fn turn_by_turn(&self, route: &Route) -> Vec<Instruction> {
+        let roads: Vec<(RoadID, bool)> = route
+            .steps
+            .iter()
+            .filter_map(|step| match step {
+                PathStep::Road { road, forwards } => Some((*road, *forwards)),
+                _ => None,
+            })
+            .collect();
+
+        let mut instructions: Vec<Instruction> = Vec::new();
+        // Per instruction, the length of each InfraType
+        let mut infra_lengths: Vec<Vec<(InfraType, f64)>> = Vec::new();
+        for (idx, (r, forwards)) in roads.iter().enumerate() {
+            let road = &self.graph.roads[r.0];
+            let name = road.osm_tags.get("name").cloned();
+            let prev = if idx == 0 { None } else { Some(roads[idx - 1]) };
+
+            let (crosses_non_high_los, leaves_network) = match prev {
+                Some(prev) => self.movement_warnings(prev, *r),
+                None => (false, false),
+            };
+
+            let turn_angle = match prev {
+                Some(prev) => self.turn_angle(prev, (*r, *forwards)),
+                None => 0.0,
+            };
+            let continues = match (instructions.last(), &name) {
+                (Some(last), Some(name)) => last.name.as_ref() == Some(name),
+                (Some(last), None) => {
+                    last.name.is_none() && classify_turn(turn_angle) == Turn::Straight
+                }
+                (None, _) => false,
+            };
+            if continues {
+                let last = instructions.last_mut().unwrap();
+                last.length += road.length_meters;
+                last.los = last.los.max(self.los[r.0]);
+                last.crosses_non_high_los |= crosses_non_high_los;
+                last.leaves_network |= leaves_network;
+            } else {
+                instructions.push(Instruction {
+                    name,
+                    length: road.length_meters,
+                    way: road.way.to_string(),
+                    infra_type: self.get_infra_type(*r),
+                    los: self.los[r.0],
+                    turn: if prev.is_some() {
+                        classify_turn(turn_angle)
+                    } else {
+                        Turn::Start
+                    },
+                    turn_angle,
+                    crosses_non_high_los,
+                    leaves_network,
+                });
+                infra_lengths.push(Vec::new());
+            }
+
+            let lengths = infra_lengths.last_mut().unwrap();
+            let infra_type = self.get_infra_type(*r);
+            match lengths.iter_mut().find(|(x, _)| *x == infra_type) {
+                Some((_, length)) => *length += road.length_meters,
+                None => lengths.push((infra_type, road.length_meters)),
+            }
+        }
+
+        for (instruction, lengths) in instructions.iter_mut().zip(infra_lengths) {
+            if let Some((infra_type, _)) = lengths
+                .into_iter()
+                .max_by(|(_, len1), (_, len2)| len1.total_cmp(len2))
+            {
+                instruction.infra_type = infra_type;
+            }
+        }
+
+        instructions
+    }
+
+    /// (crosses a road without high LoS, leaves the network) when moving between two roads
+    fn movement_warnings(&self, (r1, forwards1): (RoadID, bool), r2: RoadID) -> (bool, bool) {
+        let road1 = &self.graph.roads[r1.0];
+        let i = if forwards1 { road1.dst_i } else { road1.src_i };
+        let crosses_non_high_los = self
+            .unprotected_crossed_roads(i, r1, r2, self.has_crossing(i))
+            .into_iter()
+            .any(|r| self.los[r.0] != LevelOfService::High);
+        let leaves_network = self.infra_types[r1.0].is_some() && self.infra_types[r2.0].is_none();
+        (crosses_non_high_los, leaves_network)
+    }
+
+    /// Compares the bearing at the end of the first road with the start of the second
+    fn turn_angle(&self, (r1, forwards1): (RoadID, bool), (r2, forwards2): (RoadID, bool)) -> f64 {
+        let pts1 = &self.graph.roads[r1.0].linestring.0;
+        let pts2 = &self.graph.roads[r2.0].linestring.0;
+        let (a, b) = if forwards1 {
+            (pts1[pts1.len() - 2], pts1[pts1.len() - 1])
+        } else {
+            (pts1[1], pts1[0])
+        };
+        let (c, d) = if forwards2 {
+            (pts2[0], pts2[1])
+        } else {
+            (pts2[pts2.len() - 1], pts2[pts2.len() - 2])
+        };
+        let before = Haversine.bearing(self.to_wgs84_pt(a), self.to_wgs84_pt(b));
+        let after = Haversine.bearing(self.to_wgs84_pt(c), self.to_wgs84_pt(d));
+        normalize_angle(after - before)
+    }
+
+    fn to_wgs84_pt(&self, pt: Coord) -> Point {
+        self.graph.mercator.pt_to_wgs84(pt).into()
+    }
+}
+
+/// Into the range (-180, 180]
+fn normalize_angle(degrees: f64) -> f64 {
+    let x = degrees.rem_euclid(360.0);
+    if x > 180.0 {
+        x - 360.0
+    } else {
+        x
+    }
+}
+
+/// Thresholds in degrees are made up
+fn classify_turn(angle: f64) -> Turn {
+    let magnitude = angle.abs();
+    if magnitude < 20.0 {
+        Turn::Straight
+    } else if magnitude >= 160.0 {
+        Turn::UTurn
+    } else if angle > 0.0 {
+        if magnitude < 60.0 {
+            Turn::SlightRight
+        } else if magnitude < 120.0 {
+            Turn::Right
+        } else {
+            Turn::SharpRight
+        }
+    } else if magnitude < 60.0 {
+        Turn::SlightLeft
+    } else if magnitude < 120.0 {
+        Turn::Left
+    } else {
+        Turn::SharpLeft
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_classify_turn() {
+        assert_eq!(classify_turn(normalize_angle(90.0 - 80.0)), Turn::Straight);
+        // North to east
+        assert_eq!(classify_turn(normalize_angle(90.0 - 0.0)), Turn::Right);
+        // North-ish to west-ish, crossing 0
+        assert_eq!(classify_turn(normalize_angle(280.0 - 10.0)), Turn::Left);
+        assert_eq!(
+            classify_turn(normalize_angle(10.0 - 330.0)),
+            Turn::SlightRight
+        );
+        assert_eq!(classify_turn(-140.0), Turn::SharpLeft);
+        assert_eq!(classify_turn(135.0), Turn::SharpRight);
+        assert_eq!(classify_turn(180.0), Turn::UTurn);
+        assert_eq!(classify_turn(-170.0), Turn::UTurn);
+    }
+}
@@ -1,11 +1,16 @@
-use std::collections::BTreeSet;
+use std::collections::{BTreeSet, BinaryHeap, HashMap, HashSet};
 
+use anyhow::Result;
 use geo::{BoundingRect, Geometry, GeometryCollection, Rect};
-use geojson::FeatureCollection;
+use geojson::{Feature, FeatureCollection};
 use graph::{IntersectionID, RoadID};
 use petgraph::graphmap::UnGraphMap;
+use utils::PriorityQueueItem;
 
-use crate::{utils::into_object_value, MapModel};
+use crate::existing::classify_existing_osm_infra;
+use crate::reachable::{meters, roads_to_steps, route_input_from_roads};
+use crate::routes::glue_route_wgs84;
+use crate::{utils::into_object_value, MapModel, Tier};
 
 impl MapModel {
     // Fast enough to calculate immediately
@@ -89,3 +94,213 @@ fn nodes_to_edges(map: &MapModel, nodes: Vec<IntersectionID>) -> BTreeSet<RoadID
     edges.retain(|r| map.infra_types[r.0].is_some());
     edges
 }
+
+/// A proposed route joining one or more islands to the main network
+struct ComponentLink {
+    roads: Vec<RoadID>,
+    /// Only counting roads without infrastructure already
+    new_length: f64,
+    components_merged: usize,
+}
+
+impl ComponentLink {
+    fn length_per_component(&self) -> f64 {
+        self.new_length / (self.components_merged as f64)
+    }
+}
+
+impl MapModel {
+    /// Proposes routes that join every island of the network to the largest component, as
+    /// FeatureCollection<LineString, SetRouteInput & { rank, new_length_meters,
+    /// components_merged, length_per_component }>. Each link is the cheapest per component merged
+    /// at the time, assuming all earlier links are built, so later links may depend on earlier
+    /// ones. They're ranked in that order.
+    ///
+    /// With `best_los`, links prefer roads with a better level of service over shorter ones.
+    /// Either way, roads with existing infrastructure or high demand are preferred.
+    pub fn suggest_component_links(&self, best_los: bool, limit: usize) -> Result<String> {
+        let mut graph: UnGraphMap<IntersectionID, RoadID> = UnGraphMap::new();
+        for (idx, road) in self.graph.roads.iter().enumerate() {
+            if self.infra_types[idx].is_some() {
+                graph.add_edge(road.src_i, road.dst_i, road.id);
+            }
+        }
+        let mut components = petgraph::algo::kosaraju_scc(&graph);
+        if components.len() < 2 {
+            bail!("The network is already connected");
+        }
+        components.sort_by_cached_key(|nodes| {
+            nodes_to_edges(self, nodes.clone())
+                .into_iter()
+                .map(|r| self.graph.roads[r.0].length_meters)
+                .sum::<f64>()
+                .round() as usize
+        });
+        // Start from the largest component
+        let mut connected: HashSet<IntersectionID> =
+            components.pop().unwrap().into_iter().collect();
+        let mut island_of: HashMap<IntersectionID, usize> = HashMap::new();
+        for (idx, nodes) in components.iter().enumerate() {
+            for i in nodes {
+                island_of.insert(*i, idx);
+            }
+        }
+
+        let mut links = Vec::new();
+        while links.len() < limit {
+            let Some(link) = self.best_component_link(&connected, &island_of, best_los) else {
+                break;
+            };
+            // Everything along the path is now connected, including whole islands passed through
+            for r in &link.roads {
+                let road = &self.graph.roads[r.0];
+                for i in [road.src_i, road.dst_i] {
+                    if let Some(idx) = island_of.get(&i).cloned() {
+                        connected.extend(components[idx].iter().cloned());
+                        island_of.retain(|_, x| *x != idx);
+                    }
+                    connected.insert(i);
+                }
+            }
+            links.push(link);
+            if island_of.is_empty() {
+                break;
+            }
+        }
+
+        let mut features = Vec::new();
+        for (rank, link) in links.into_iter().enumerate() {
+            let roads = roads_to_steps(&self.graph, link.roads)?;
+            let linestring_wgs84 = glue_route_wgs84(&self.graph, &roads);
+            let mut f = Feature::from(geojson::Geometry::from(&linestring_wgs84));
+            f.properties = Some(into_object_value(serde_json::to_value(
+                route_input_from_roads(
+                    &self.graph,
+                    &roads,
+                    "link between network components".to_string(),
+                    String::new(),
+                    Tier::LocalAccess,
+                ),
+            )?));
+            f.set_property("rank", rank + 1);
+            f.set_property("new_length_meters", link.new_length);
+            f.set_property("components_merged", link.components_merged);
+            f.set_property("length_per_component", link.length_per_component());
+            features.push(f);
+        }
+
+        Ok(serde_json::to_string(&FeatureCollection {
+            features,
+            bbox: None,
+            foreign_members: None,
+        })?)
+    }
+
+    /// Search outwards from the connected network, treating existing islands as free to travel
+    /// through. Return the link to whichever island is cheapest per component merged.
+    fn best_component_link(
+        &self,
+        connected: &HashSet<IntersectionID>,
+        island_of: &HashMap<IntersectionID, usize>,
+        best_los: bool,
+    ) -> Option<ComponentLink> {
+        let mut backrefs: HashMap<IntersectionID, (RoadID, IntersectionID)> = HashMap::new();
+        let mut best: HashMap<IntersectionID, usize> = HashMap::new();
+        // The first intersection reached in each island
+        let mut reached_islands: HashMap<usize, IntersectionID> = HashMap::new();
+        let mut queue: BinaryHeap<PriorityQueueItem<usize, IntersectionID>> = BinaryHeap::new();
+        // The cheapest cost pushed so far for each intersection. Backrefs are only set when this
+        // improves, so they always describe the path that was costed.
+        for i in connected {
+            best.insert(*i, 0);
+            queue.push(PriorityQueueItem::new(0, *i));
+        }
+
+        while let Some(item) = queue.pop() {
+            let i1 = item.value;
+            // A cheaper path was pushed later
+            if best.get(&i1).is_some_and(|cost| *cost < item.cost) {
+                continue;
+            }
+            if let Some(idx) = island_of.get(&i1) {
+                reached_islands.entry(*idx).or_insert(i1);
+            }
+
+            for r in &self.graph.intersections[i1.0].roads {
+                let road = &self.graph.roads[r.0];
+                let i2 = if road.src_i == i1 {
+                    road.dst_i
+                } else {
+                    road.src_i
+                };
+                if connected.contains(&i2) {
+                    continue;
+                }
+                let cost = item.cost + self.component_link_cost(*r, best_los);
+                if best.get(&i2).is_some_and(|x| *x <= cost) {
+                    continue;
+                }
+                best.insert(i2, cost);
+                backrefs.insert(i2, (*r, i1));
+                queue.push(PriorityQueueItem::new(cost, i2));
+            }
+        }
+
+        let mut result: Option<ComponentLink> = None;
+        for end in reached_islands.into_values() {
+            let mut roads = Vec::new();
+            let mut islands = HashSet::new();
+            let mut new_length = 0.0;
+            let mut current = end;
+            while let Some((r, prev)) = backrefs.get(&current) {
+                roads.push(*r);
+                if self.infra_types[r.0].is_none() {
+                    new_length += self.graph.roads[r.0].length_meters;
+                }
+                if let Some(idx) = island_of.get(&current) {
+                    islands.insert(*idx);
+                }
+                current = *prev;
+            }
+            // Going from the network to the island is a bit more natural
+            roads.reverse();
+            let link = ComponentLink {
+                roads,
+                new_length,
+                components_merged: islands.len(),
+            };
+
+            let better = match &result {
+                Some(x) => link.length_per_component() < x.length_per_component(),
+                None => true,
+            };
+            if better {
+                result = Some(link);
+            }
+        }
+        result
+    }
+
+    /// The cost of crossing one road while linking components. Roads that're already part of the
+    /// network are free. The preferences are made up.
+    fn component_link_cost(&self, r: RoadID, best_los: bool) -> usize {
+        if self.infra_types[r.0].is_some() {
+            return 0;
+        }
+        let mut cost = self.graph.roads[r.0].length_meters;
+        if best_los {
+            cost *= self.los[r.0].penalty();
+        }
+        if classify_existing_osm_infra(self.is_offroad[r.0], &self.graph.roads[r.0].osm_tags)
+            .is_some()
+        {
+            cost *= 0.5;
+        }
+        if self.precalculated_demands[r.0] >= self.high_demand_threshold {
+            cost *= 0.7;
+        } else if self.precalculated_demands[r.0] >= self.medium_demand_threshold {
+            cost *= 0.85;
+        }
+        meters(cost)
+    }
+}
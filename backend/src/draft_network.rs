@@ -0,0 +1,221 @@
+use std::collections::{BinaryHeap, HashSet};
+
+use anyhow::Result;
+use graph::RoadID;
+use serde::Serialize;
+use utils::PriorityQueueItem;
+
+use crate::MapModel;
+
+/// What a draft network covers
+#[derive(Default, Serialize)]
+struct DraftSummary {
+    num_roads: usize,
+    length_meters: f64,
+    cost: f64,
+    demand_covered: usize,
+}
+
+impl MapModel {
+    /// Greedily grow a connected network from the existing routes (or the highest demand road,
+    /// if there are none yet), picking whichever neighboring road covers the most demand and POIs
+    /// per meter or pound spent, until the budget runs out. The new routes use the best
+    /// infrastructure type and an automatic tier, like `import_arterial_roads`. Returns a summary
+    /// of what was added.
+    pub fn generate_draft_network(
+        &mut self,
+        max_length_meters: Option<f64>,
+        max_cost: Option<f64>,
+    ) -> Result<String> {
+        if max_length_meters.is_none() && max_cost.is_none() {
+            bail!("A length or cost budget is needed");
+        }
+
+        let poi_roads = self.draft_poi_roads();
+        let score = |r: RoadID| {
+            let mut score = self.precalculated_demands[r.0];
+            if poi_roads.contains(&r) {
+                score += self.medium_demand_threshold;
+            }
+            score
+        };
+        // With only a length budget, spend it on length; otherwise spend it on money
+        let spend = |r: RoadID| {
+            let length = self.graph.roads[r.0].length_meters;
+            if max_cost.is_some() {
+                length / 1000.0 * self.best_infra_type(r).cost_per_km()
+            } else {
+                length
+            }
+        };
+
+        let density = |r: RoadID| {
+            // PriorityQueueItem is a min-heap, so invert the score
+            let x = (1000.0 * score(r) as f64 / spend(r).max(1.0)).round() as usize;
+            usize::MAX - x
+        };
+        let length_and_cost = |r: RoadID| {
+            let length = self.graph.roads[r.0].length_meters;
+            (
+                length,
+                length / 1000.0 * self.best_infra_type(r).cost_per_km(),
+            )
+        };
+
+        let used_roads = self.used_roads();
+        let seeds: Vec<RoadID> = if used_roads.is_empty() {
+            (0..self.graph.roads.len())
+                .map(RoadID)
+                .max_by_key(|r| score(*r))
+                .into_iter()
+                .collect()
+        } else {
+            used_roads.iter().cloned().collect()
+        };
+        let chosen = grow_draft(
+            &seeds,
+            &used_roads,
+            |r| self.draft_neighbors(r),
+            density,
+            length_and_cost,
+            max_length_meters,
+            max_cost,
+        );
+
+        let mut summary = DraftSummary::default();
+        for r in &chosen {
+            let (length, cost) = length_and_cost(*r);
+            summary.num_roads += 1;
+            summary.length_meters += length;
+            summary.cost += cost;
+            summary.demand_covered += self.precalculated_demands[r.0];
+        }
+        info!(
+            "Draft network has {} roads, {}m long",
+            summary.num_roads, summary.length_meters
+        );
+
+        let imports = chosen
+            .into_iter()
+            .map(|r| (r, self.best_infra_type(r), self.tier_for_import(r)))
+            .collect();
+        self.import_roads(imports, "generated draft network");
+
+        Ok(serde_json::to_string(&summary)?)
+    }
+
+    fn draft_neighbors(&self, r: RoadID) -> Vec<RoadID> {
+        let road = &self.graph.roads[r.0];
+        [road.src_i, road.dst_i]
+            .into_iter()
+            .flat_map(|i| self.graph.intersections[i.0].roads.iter().cloned())
+            .filter(|r2| *r2 != r)
+            .collect()
+    }
+
+    /// Roads next to the main kinds of POIs. Settlements and greenspaces cover too many roads to
+    /// be useful here.
+    fn draft_poi_roads(&self) -> HashSet<RoadID> {
+        let mut roads = HashSet::new();
+        roads.extend(self.schools.iter().map(|x| x.road));
+        roads.extend(self.gp_hospitals.iter().map(|x| x.road));
+        roads.extend(self.railway_stations.iter().map(|x| x.road));
+        for x in &self.town_centres {
+            roads.extend(x.roads.iter().cloned());
+        }
+        roads
+    }
+}
+
+/// Greedily grow outwards from the seeds, always taking the queued road with the best density
+/// (lowest first), skipping anything that would exceed a budget. Seeds already in `used_roads`
+/// aren't chosen again, but their neighbors are queued. Returns the chosen roads.
+fn grow_draft<N, D, LC>(
+    seeds: &[RoadID],
+    used_roads: &HashSet<RoadID>,
+    neighbors: N,
+    density: D,
+    length_and_cost: LC,
+    max_length_meters: Option<f64>,
+    max_cost: Option<f64>,
+) -> HashSet<RoadID>
+where
+    N: Fn(RoadID) -> Vec<RoadID>,
+    D: Fn(RoadID) -> usize,
+    LC: Fn(RoadID) -> (f64, f64),
+{
+    let mut chosen: HashSet<RoadID> = HashSet::new();
+    let mut queue: BinaryHeap<PriorityQueueItem<usize, RoadID>> = BinaryHeap::new();
+    let mut queued: HashSet<RoadID> = HashSet::new();
+    let push_neighbors =
+        |r: RoadID,
+         queued: &mut HashSet<RoadID>,
+         queue: &mut BinaryHeap<PriorityQueueItem<usize, RoadID>>| {
+            for r2 in neighbors(r) {
+                if used_roads.contains(&r2) || queued.contains(&r2) {
+                    continue;
+                }
+                queued.insert(r2);
+                queue.push(PriorityQueueItem::new(density(r2), r2));
+            }
+        };
+
+    for r in seeds {
+        queued.insert(*r);
+        if used_roads.contains(r) {
+            push_neighbors(*r, &mut queued, &mut queue);
+        } else {
+            queue.push(PriorityQueueItem::new(density(*r), *r));
+        }
+    }
+
+    let mut total_length = 0.0;
+    let mut total_cost = 0.0;
+    while let Some(item) = queue.pop() {
+        let r = item.value;
+        let (length, cost) = length_and_cost(r);
+        // Something smaller might still fit
+        if max_length_meters.is_some_and(|max| total_length + length > max)
+            || max_cost.is_some_and(|max| total_cost + cost > max)
+        {
+            continue;
+        }
+
+        chosen.insert(r);
+        total_length += length;
+        total_cost += cost;
+        push_neighbors(r, &mut queued, &mut queue);
+    }
+    chosen
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::InfraType;
+
+    #[test]
+    fn test_cost_budget_bounds_quiet_grid() {
+        // A 10x10 grid of 100m quiet streets, all suited to mixed traffic. Road i is at (i % 10,
+        // i / 10) and touches its 4 neighbors.
+        let neighbors = |r: RoadID| {
+            let (x, y) = ((r.0 % 10) as isize, (r.0 / 10) as isize);
+            [(x - 1, y), (x + 1, y), (x, y - 1), (x, y + 1)]
+                .into_iter()
+                .filter(|(x, y)| (0..10).contains(x) && (0..10).contains(y))
+                .map(|(x, y)| RoadID((y * 10 + x) as usize))
+                .collect()
+        };
+        let cost_per_road = 0.1 * InfraType::MixedTraffic.cost_per_km();
+        let chosen = grow_draft(
+            &[RoadID(0)],
+            &HashSet::new(),
+            neighbors,
+            |_| 0,
+            |_| (100.0, cost_per_road),
+            None,
+            Some(5.0 * cost_per_road),
+        );
+        assert_eq!(chosen.len(), 5);
+    }
+}
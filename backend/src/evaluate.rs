@@ -1,11 +1,14 @@
+use std::collections::HashSet;
+
 use anyhow::Result;
 use geo::{Coord, Distance, Euclidean, Length};
 use geojson::FeatureCollection;
-use graph::PathStep;
+use graph::{PathStep, RoadID, Route, Timer};
 use serde::Serialize;
 
 use crate::{
-    routes::gradient_group, utils::into_object_value, InfraType, LevelOfService, MapModel,
+    route_report::LengthBreakdown, routes::gradient_group, travel_time::RiderType,
+    utils::into_object_value, InfraType, LevelOfService, MapModel,
 };
 
 pub enum Breakdown {
@@ -16,7 +19,13 @@ pub enum Breakdown {
 }
 
 impl MapModel {
-    pub fn evaluate_route(&self, pt1: Coord, pt2: Coord, breakdown: Breakdown) -> Result<String> {
+    pub fn evaluate_route(
+        &self,
+        pt1: Coord,
+        pt2: Coord,
+        breakdown: Breakdown,
+        rider: RiderType,
+    ) -> Result<String> {
         assert!(self.quiet_router_ok);
 
         let profile = self.graph.profile_names["bicycle_direct"];
@@ -38,6 +47,7 @@ impl MapModel {
                 });
             }
         }
+        let instructions = self.turn_by_turn(&route);
 
         let mut features = Vec::new();
         match breakdown {
@@ -98,11 +108,260 @@ impl MapModel {
             foreign_members: Some(into_object_value(serde_json::json!({
                 "direct_bike_length": Euclidean.length(&full_route_linestring),
                 "quiet_bike_length": Euclidean.length(&quiet_bike_linestring),
+                "direct_bike_minutes": self.route_travel_time_minutes(&route, rider),
+                "quiet_bike_minutes": self.route_travel_time_minutes(&quiet_bike_route, rider),
                 "straight_line_length": straight_line_length,
                 "directions": directions,
+                "instructions": instructions,
+            }))),
+        })?)
+    }
+}
+
+impl MapModel {
+    /// Route between two points on the current network and on the baseline network, with only
+    /// existing infrastructure (the one used for `baseline_stats`). Both use quiet routing.
+    /// Returns a FeatureCollection of LineStrings split by LoS, with a `kind` of "before" or
+    /// "after", and a `JourneySummary` of each in the foreign members.
+    pub fn evaluate_route_before_after(
+        &mut self,
+        pt1: Coord,
+        pt2: Coord,
+        timer: &mut Timer,
+    ) -> Result<String> {
+        let mut features = Vec::new();
+        let before = self.with_baseline_network(timer, |model| {
+            model.summarise_quiet_journey(pt1, pt2, "before", &mut features)
+        })?;
+        self.recalculate_quiet_router(timer);
+        let after = self.summarise_quiet_journey(pt1, pt2, "after", &mut features)?;
+
+        Ok(serde_json::to_string(&FeatureCollection {
+            features,
+            bbox: None,
+            foreign_members: Some(into_object_value(serde_json::json!({
+                "quiet_length_change": after.length_meters - before.length_meters,
+                "quiet_minutes_change": after.minutes - before.minutes,
+                "before": before,
+                "after": after,
+            }))),
+        })?)
+    }
+
+    /// Temporarily replace all routes and crossings with the existing infrastructure imported when
+    /// calculating `baseline_stats`, calculate something, then restore everything. The quiet router
+    /// is left needing recalculation.
+    pub(crate) fn with_baseline_network<T, F: FnOnce(&MapModel) -> T>(
+        &mut self,
+        timer: &mut Timer,
+        f: F,
+    ) -> T {
+        let routes = std::mem::take(&mut self.routes);
+        let id_counter = self.id_counter;
+        // Planned crossings aren't part of the baseline either
+        let crossings = std::mem::take(&mut self.crossings);
+        self.recalculate_after_edits();
+        let only_some_infra_types = true;
+        self.import_existing_routes(only_some_infra_types);
+        self.recalculate_quiet_router(timer);
+
+        let result = f(self);
+
+        self.routes = routes;
+        self.id_counter = id_counter;
+        self.crossings = crossings;
+        self.recalculate_after_edits();
+        result
+    }
+
+    fn summarise_quiet_journey(
+        &self,
+        pt1: Coord,
+        pt2: Coord,
+        kind: &str,
+        features: &mut Vec<geojson::Feature>,
+    ) -> Result<JourneySummary> {
+        assert!(self.quiet_router_ok);
+
+        let profile = self.graph.profile_names["bicycle_quiet"];
+        let start = self.graph.snap_to_road(pt1, profile);
+        let end = self.graph.snap_to_road(pt2, profile);
+        let route = self.graph.routers[profile.0].route(&self.graph, start, end)?;
+
+        for (linestring, los) in route.split_linestrings(&self.graph, |r| self.los[r.0]) {
+            let mut f = self.graph.mercator.to_wgs84_gj(&linestring);
+            f.set_property("kind", kind);
+            f.set_property("los", serde_json::to_value(los)?);
+            features.push(f);
+        }
+
+        Ok(self.summarise_journey(&route))
+    }
+
+    /// Finds up to `k` different quiet routes between two points. After each route is found, the
+    /// roads along it are penalized, so the next search prefers a different corridor. Routes more
+    /// than `max_stretch` (at least 1) times longer than the first are discarded.
+    ///
+    /// Returns a FeatureCollection of LineStrings split by LoS and InfraType, with an
+    /// `alternative` index, and a list of `JourneySummary` with `shared_with_first` (the fraction
+    /// of the length on roads used by the first route) in the foreign members.
+    pub fn evaluate_route_alternatives(
+        &mut self,
+        pt1: Coord,
+        pt2: Coord,
+        k: usize,
+        max_stretch: f64,
+        timer: &mut Timer,
+    ) -> Result<String> {
+        if k == 0 {
+            bail!("Ask for at least one route");
+        }
+        if max_stretch.is_nan() || max_stretch < 1.0 {
+            bail!("max_stretch must be at least 1, not {max_stretch}");
+        }
+        self.recalculate_quiet_router(timer);
+        let result = self.find_alternatives(pt1, pt2, k, max_stretch, timer);
+        // Undo the penalties
+        self.quiet_router_ok = false;
+        self.recalculate_quiet_router(timer);
+        let (features, summaries) = result?;
+
+        Ok(serde_json::to_string(&FeatureCollection {
+            features,
+            bbox: None,
+            foreign_members: Some(into_object_value(serde_json::json!({
+                "alternatives": summaries,
             }))),
         })?)
     }
+
+    fn find_alternatives(
+        &mut self,
+        pt1: Coord,
+        pt2: Coord,
+        k: usize,
+        max_stretch: f64,
+        timer: &mut Timer,
+    ) -> Result<(Vec<geojson::Feature>, Vec<serde_json::Value>)> {
+        // Made up. Compounds each time a road is used again.
+        let penalty = 1.5;
+        // Give up if penalizing keeps finding duplicates or long detours
+        let max_attempts = 2 * k;
+
+        let profile = self.graph.profile_names["bicycle_quiet"];
+        let start = self.graph.snap_to_road(pt1, profile);
+        let end = self.graph.snap_to_road(pt2, profile);
+
+        let mut features = Vec::new();
+        let mut summaries = Vec::new();
+        let mut found: Vec<Vec<RoadID>> = Vec::new();
+        let mut first_roads: HashSet<RoadID> = HashSet::new();
+        let mut first_length = 0.0;
+
+        for attempt in 0..max_attempts {
+            let route = self.graph.routers[profile.0].route(&self.graph, start, end)?;
+            let roads: Vec<RoadID> = route
+                .steps
+                .iter()
+                .filter_map(|step| match step {
+                    PathStep::Road { road, .. } => Some(*road),
+                    _ => None,
+                })
+                .collect();
+
+            if !found.contains(&roads) {
+                let summary = self.summarise_journey(&route);
+                // Only the unpenalized route is the baseline for stretch
+                if attempt == 0 {
+                    first_roads = roads.iter().cloned().collect();
+                    first_length = summary.length_meters;
+                }
+                if summary.length_meters <= max_stretch * first_length {
+                    let alternative = found.len();
+                    for (linestring, (los, infra_type)) in route
+                        .split_linestrings(&self.graph, |r| (self.los[r.0], self.get_infra_type(r)))
+                    {
+                        let mut f = self.graph.mercator.to_wgs84_gj(&linestring);
+                        f.set_property("alternative", alternative);
+                        f.set_property("los", serde_json::to_value(los)?);
+                        f.set_property("infra_type", serde_json::to_value(infra_type)?);
+                        features.push(f);
+                    }
+
+                    let shared_length: f64 = roads
+                        .iter()
+                        .filter(|r| first_roads.contains(r))
+                        .map(|r| self.graph.roads[r.0].length_meters)
+                        .sum();
+                    let mut value = serde_json::to_value(&summary)?;
+                    value["shared_with_first"] = if summary.length_meters > 0.0 {
+                        (shared_length / summary.length_meters).into()
+                    } else {
+                        1.0.into()
+                    };
+                    summaries.push(value);
+                    found.push(roads.clone());
+                    if found.len() == k {
+                        break;
+                    }
+                }
+            }
+
+            for r in &roads {
+                let road = &mut self.graph.roads[r.0];
+                road.cost = vec![road.cost[0].mul_f64(penalty)];
+            }
+            timer.step("penalize the previous alternative");
+            self.graph.routers[profile.0].update_costs(&self.graph.roads, profile);
+        }
+
+        Ok((features, summaries))
+    }
+
+    fn summarise_journey(&self, route: &Route) -> JourneySummary {
+        let linestring = route.linestring(&self.graph);
+        let straight_line_length = Euclidean.distance(
+            linestring.points().next().unwrap(),
+            linestring.points().last().unwrap(),
+        );
+
+        let roads: Vec<RoadID> = route
+            .steps
+            .iter()
+            .filter_map(|step| match step {
+                PathStep::Road { road, .. } => Some(*road),
+                _ => None,
+            })
+            .collect();
+        let length_meters = roads
+            .iter()
+            .map(|r| self.graph.roads[r.0].length_meters)
+            .sum::<f64>();
+
+        JourneySummary {
+            length_meters,
+            breakdown: self.length_breakdown(roads.into_iter()),
+            minutes: self.route_travel_time_minutes(route, RiderType::Standard),
+            directness: if straight_line_length > 0.0 {
+                Some(length_meters / straight_line_length)
+            } else {
+                None
+            },
+        }
+    }
+}
+
+/// One journey on either the current or the baseline network
+#[derive(Serialize)]
+struct JourneySummary {
+    length_meters: f64,
+    #[serde(flatten)]
+    breakdown: LengthBreakdown,
+    /// The riding time for a standard rider
+    minutes: f64,
+    /// The route length divided by the straight-line distance. None if the start and end are the
+    /// same.
+    directness: Option<f64>,
 }
 
 #[derive(Serialize)]
@@ -0,0 +1,227 @@
+use std::collections::{BinaryHeap, HashMap, HashSet};
+
+use anyhow::Result;
+use geojson::{Feature, FeatureCollection, Geometry};
+use graph::{IntersectionID, RoadID};
+use utils::PriorityQueueItem;
+
+use crate::reachable::{meters, roads_to_steps, route_input_from_roads};
+use crate::routes::glue_route_wgs84;
+use crate::{utils::into_object_value, MapModel, Tier, POI_KINDS};
+
+/// What a dangling end of the network stops short of
+enum GapTarget {
+    /// Another part of the network
+    Network(IntersectionID),
+    /// An unreachable POI, described by kind and index
+    Poi(String, usize),
+}
+
+impl MapModel {
+    /// Find dead-ends of the network within `max_distance_meters` of another part of the
+    /// network, or of a POI that isn't reachable yet. Returns a FeatureCollection<LineString,
+    /// SetRouteInput & { kind, gap_meters, poi_kind?, poi_idx? }> of suggested connections,
+    /// shortest first.
+    pub fn find_network_gaps(&self, max_distance_meters: f64) -> Result<String> {
+        // Which route is each road part of?
+        let mut route_per_road: HashMap<RoadID, usize> = HashMap::new();
+        for (id, route) in &self.routes {
+            for (r, _) in &route.roads {
+                route_per_road.insert(*r, *id);
+            }
+        }
+
+        let reachable = self.get_reachable_network();
+        let mut unreachable_pois: HashMap<RoadID, (String, usize)> = HashMap::new();
+        // Settlements cover whole towns, rather than one destination a dead-end could stop short of
+        for kind in POI_KINDS.into_iter().filter(|kind| *kind != "settlements") {
+            for (idx, roads) in self.get_pois_of_kind(kind)?.into_iter().enumerate() {
+                if reachable.covers_any(&roads) {
+                    continue;
+                }
+                for r in roads {
+                    unreachable_pois.insert(r, (kind.to_string(), idx));
+                }
+            }
+        }
+
+        let mut gaps: Vec<(f64, Vec<RoadID>, GapTarget)> = Vec::new();
+        let mut seen_pairs: HashSet<(IntersectionID, IntersectionID)> = HashSet::new();
+        for i in self.network_dead_ends() {
+            for (roads, target) in
+                self.gaps_from_dead_end(i, max_distance_meters, &route_per_road, &unreachable_pois)
+            {
+                // Two dead-ends facing each other only need to be fixed once
+                if let GapTarget::Network(i2) = target {
+                    if !seen_pairs.insert((i.min(i2), i.max(i2))) {
+                        continue;
+                    }
+                }
+                let length = roads
+                    .iter()
+                    .map(|r| self.graph.roads[r.0].length_meters)
+                    .sum();
+                gaps.push((length, roads, target));
+            }
+        }
+        gaps.sort_by(|a, b| a.0.total_cmp(&b.0));
+
+        let mut features = Vec::new();
+        for (length, roads, target) in gaps {
+            let roads = roads_to_steps(&self.graph, roads)?;
+            let linestring_wgs84 = glue_route_wgs84(&self.graph, &roads);
+            let mut f = Feature::from(Geometry::from(&linestring_wgs84));
+            f.properties = Some(into_object_value(serde_json::to_value(
+                route_input_from_roads(
+                    &self.graph,
+                    &roads,
+                    "gap in the network".to_string(),
+                    String::new(),
+                    Tier::LocalAccess,
+                ),
+            )?));
+            f.set_property("gap_meters", length);
+            match target {
+                GapTarget::Network(_) => {
+                    f.set_property("kind", "network");
+                }
+                GapTarget::Poi(kind, idx) => {
+                    f.set_property("kind", "poi");
+                    f.set_property("poi_kind", kind);
+                    f.set_property("poi_idx", idx);
+                }
+            }
+            features.push(f);
+        }
+
+        Ok(serde_json::to_string(&FeatureCollection {
+            features,
+            bbox: None,
+            foreign_members: None,
+        })?)
+    }
+
+    /// Intersections with exactly one road that's part of the network
+    fn network_dead_ends(&self) -> Vec<IntersectionID> {
+        self.graph
+            .intersections
+            .iter()
+            .filter(|i| {
+                i.roads
+                    .iter()
+                    .filter(|r| self.infra_types[r.0].is_some())
+                    .count()
+                    == 1
+            })
+            .map(|i| i.id)
+            .collect()
+    }
+
+    /// Search from a dead-end along roads not in the network. Returns the path to the closest
+    /// other part of the network and the closest unreachable POI, if they're close enough.
+    fn gaps_from_dead_end(
+        &self,
+        start: IntersectionID,
+        max_distance_meters: f64,
+        route_per_road: &HashMap<RoadID, usize>,
+        unreachable_pois: &HashMap<RoadID, (String, usize)>,
+    ) -> Vec<(Vec<RoadID>, GapTarget)> {
+        let limit = meters(max_distance_meters);
+        let start_road = *self.graph.intersections[start.0]
+            .roads
+            .iter()
+            .find(|r| self.infra_types[r.0].is_some())
+            .unwrap();
+        let start_route = route_per_road.get(&start_road);
+        // Reaching the same route again via a loop isn't a gap
+        let is_other_network = |i: IntersectionID| {
+            self.graph.intersections[i.0].roads.iter().any(|r| {
+                self.infra_types[r.0].is_some()
+                    && *r != start_road
+                    && (start_route.is_none() || route_per_road.get(r) != start_route)
+            })
+        };
+
+        let mut backrefs: HashMap<IntersectionID, (RoadID, IntersectionID)> = HashMap::new();
+        // The cheapest cost pushed so far for each intersection. Backrefs are only set when this
+        // improves, so they always describe the path that was costed.
+        let mut best: HashMap<IntersectionID, usize> = HashMap::new();
+        // Either an intersection, or a POI road entered from an intersection. POI roads are only
+        // matched when popped, so the closest one wins.
+        let mut queue: BinaryHeap<PriorityQueueItem<usize, (IntersectionID, Option<RoadID>)>> =
+            BinaryHeap::new();
+        best.insert(start, 0);
+        queue.push(PriorityQueueItem::new(0, (start, None)));
+
+        let path_to =
+            |mut current: IntersectionID,
+             backrefs: &HashMap<IntersectionID, (RoadID, IntersectionID)>| {
+                let mut roads = Vec::new();
+                while let Some((r, prev)) = backrefs.get(&current) {
+                    roads.push(*r);
+                    current = *prev;
+                }
+                roads.reverse();
+                roads
+            };
+
+        let mut found_network = false;
+        let mut found_poi = false;
+        let mut results = Vec::new();
+        while let Some(item) = queue.pop() {
+            let (i1, poi_road) = item.value;
+            if let Some(r) = poi_road {
+                if !found_poi {
+                    let (kind, idx) = &unreachable_pois[&r];
+                    found_poi = true;
+                    let mut roads = path_to(i1, &backrefs);
+                    roads.push(r);
+                    results.push((roads, GapTarget::Poi(kind.clone(), *idx)));
+                }
+            } else {
+                // A cheaper path was pushed later
+                if best.get(&i1).is_some_and(|cost| *cost < item.cost) {
+                    continue;
+                }
+                if !found_network && i1 != start && is_other_network(i1) {
+                    found_network = true;
+                    results.push((path_to(i1, &backrefs), GapTarget::Network(i1)));
+                    // Don't search through the rest of the network
+                    continue;
+                }
+
+                for r in &self.graph.intersections[i1.0].roads {
+                    if self.infra_types[r.0].is_some() {
+                        continue;
+                    }
+                    let road = &self.graph.roads[r.0];
+                    let cost = item.cost + meters(road.length_meters);
+                    if cost > limit {
+                        continue;
+                    }
+
+                    if !found_poi && unreachable_pois.contains_key(r) {
+                        queue.push(PriorityQueueItem::new(cost, (i1, Some(*r))));
+                    }
+
+                    let i2 = if road.src_i == i1 {
+                        road.dst_i
+                    } else {
+                        road.src_i
+                    };
+                    if best.get(&i2).is_some_and(|x| *x <= cost) {
+                        continue;
+                    }
+                    best.insert(i2, cost);
+                    backrefs.insert(i2, (*r, i1));
+                    queue.push(PriorityQueueItem::new(cost, (i2, None)));
+                }
+            }
+
+            if found_network && found_poi {
+                break;
+            }
+        }
+        results
+    }
+}
@@ -0,0 +1,150 @@
+use std::collections::{BTreeMap, BinaryHeap, HashMap, HashSet};
+
+use anyhow::Result;
+use geo::{ConvexHull, MultiPoint, Point};
+use geojson::FeatureCollection;
+use graph::{IntersectionID, RoadID};
+use utils::PriorityQueueItem;
+
+use crate::reachable::meters;
+use crate::travel_time::RiderType;
+use crate::{utils::into_object_value, LevelOfService, MapModel, POI_KINDS};
+
+impl MapModel {
+    /// Finds everywhere reachable by bike from a point (Mercator) within a distance in meters or
+    /// a time in minutes. Times are for a standard rider, accounting for gradient and
+    /// infrastructure type along each road. Journeys follow the same edge costs as
+    /// `bicycle_quiet`, including its approximate junction and crossing costs, so a road counts if
+    /// the quiet path to it is within the limit. With `only_high_los`, only high LoS roads are
+    /// used.
+    ///
+    /// Returns a FeatureCollection with the convex hull of the reachable roads (`kind =
+    /// "isochrone"`) and every reachable road (`kind = "road"`, with the `distance` in meters and
+    /// `minutes` to the nearer end). The foreign members summarise the `population` of data zones
+    /// touching a reachable road, and the number of `pois` of each kind.
+    pub fn get_isochrone(
+        &self,
+        pt: Point,
+        max_distance_meters: Option<f64>,
+        max_minutes: Option<f64>,
+        only_high_los: bool,
+    ) -> Result<String> {
+        if max_distance_meters.is_some() == max_minutes.is_some() {
+            bail!("Specify exactly one of a distance or time limit");
+        }
+        let start = self.snap_to_intersection(pt, None);
+        let distances =
+            self.isochrone_distances(start, max_distance_meters, max_minutes, only_high_los);
+        if distances.is_empty() {
+            bail!("Nothing is reachable from this point");
+        }
+
+        let mut features = Vec::new();
+        let mut points = Vec::new();
+        for (r, (distance, minutes)) in &distances {
+            let road = &self.graph.roads[r.0];
+            points.extend(road.linestring.points());
+            let mut f = self.graph.mercator.to_wgs84_gj(&road.linestring);
+            f.set_property("kind", "road");
+            f.set_property("distance", *distance);
+            f.set_property("minutes", *minutes);
+            features.push(f);
+        }
+        let mut hull = self
+            .graph
+            .mercator
+            .to_wgs84_gj(&MultiPoint(points).convex_hull());
+        hull.set_property("kind", "isochrone");
+        features.insert(0, hull);
+
+        let roads: HashSet<RoadID> = distances.keys().cloned().collect();
+        let population: usize = self
+            .data_zones
+            .iter()
+            .filter(|zone| !zone.roads.is_disjoint(&roads))
+            .map(|zone| zone.population)
+            .sum();
+        let mut pois = BTreeMap::new();
+        for kind in POI_KINDS {
+            let count = self
+                .get_pois_of_kind(kind)?
+                .iter()
+                .filter(|poi| !poi.is_disjoint(&roads))
+                .count();
+            pois.insert(kind, count);
+        }
+
+        Ok(serde_json::to_string(&FeatureCollection {
+            features,
+            bbox: None,
+            foreign_members: Some(into_object_value(serde_json::json!({
+                "limit_meters": max_distance_meters,
+                "limit_minutes": max_minutes,
+                "population": population,
+                "pois": pois,
+            }))),
+        })?)
+    }
+
+    /// Search from an intersection by bicycle_quiet edge costs, tracking the real distance and riding time
+    /// along each path. Returns the (meters, minutes) to the nearer end of every reachable road.
+    fn isochrone_distances(
+        &self,
+        start: IntersectionID,
+        max_distance_meters: Option<f64>,
+        max_minutes: Option<f64>,
+        only_high_los: bool,
+    ) -> HashMap<RoadID, (f64, f64)> {
+        let mut distances: HashMap<RoadID, (f64, f64)> = HashMap::new();
+        let mut visited: HashSet<IntersectionID> = HashSet::new();
+        // The cost is quiet-weighted, but the value also tracks the real distance in cm and time
+        // in centiseconds
+        let mut queue: BinaryHeap<PriorityQueueItem<usize, (IntersectionID, usize, usize)>> =
+            BinaryHeap::new();
+        queue.push(PriorityQueueItem::new(0, (start, 0, 0)));
+
+        while let Some(item) = queue.pop() {
+            let (i1, dist1, time1) = item.value;
+            if !visited.insert(i1) {
+                continue;
+            }
+            let here = (dist1 as f64 / 100.0, time1 as f64 / 100.0 / 60.0);
+
+            for r in &self.graph.intersections[i1.0].roads {
+                if only_high_los && self.los[r.0] != LevelOfService::High {
+                    continue;
+                }
+                let road = &self.graph.roads[r.0];
+                distances
+                    .entry(*r)
+                    .and_modify(|x| {
+                        if here.0 < x.0 {
+                            *x = here;
+                        }
+                    })
+                    .or_insert(here);
+
+                let forwards = road.src_i == i1;
+                let i2 = if forwards { road.dst_i } else { road.src_i };
+                let dist2 = dist1 + meters(road.length_meters);
+                let seconds = self.road_travel_time_seconds(*r, forwards, RiderType::Standard);
+                let time2 = time1 + (seconds * 100.0).round() as usize;
+                if visited.contains(&i2)
+                    || max_distance_meters.is_some_and(|max| dist2 as f64 / 100.0 > max)
+                    || max_minutes.is_some_and(|max| time2 as f64 / 100.0 / 60.0 > max)
+                {
+                    continue;
+                }
+                // The same edge cost as bicycle_quiet, including its junction approximation
+                let cost = item.cost
+                    + meters(
+                        self.los_penalty(self.los[r.0]) * road.length_meters
+                            + self.approx_junction_cost_meters(*r),
+                    );
+                queue.push(PriorityQueueItem::new(cost, (i2, dist2, time2)));
+            }
+        }
+
+        distances
+    }
+}
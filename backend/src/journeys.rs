@@ -0,0 +1,253 @@
+use anyhow::Result;
+use geo::{Coord, Euclidean, Length};
+use geojson::FeatureCollection;
+use graph::{PathStep, Route, Timer};
+use serde::Serialize;
+
+use crate::{travel_time::RiderType, utils::into_object_value, LevelOfService, MapModel};
+
+/// One journey with any number of waypoints, in WGS84
+pub struct JourneyInput {
+    pub id: String,
+    pub waypoints: Vec<Coord>,
+}
+
+/// Metrics for one journey, summed over all legs
+#[derive(Serialize)]
+pub struct JourneyMetrics {
+    pub id: String,
+    pub direct_length: f64,
+    pub quiet_length: f64,
+    /// Riding times for a standard rider
+    pub direct_minutes: f64,
+    pub quiet_minutes: f64,
+    /// The fraction of the quiet route's length on high LoS roads, from 0 to 1
+    pub percent_high_los: f64,
+    /// The steepest gradient along the quiet route, as an absolute percent
+    pub max_gradient: f64,
+    /// If routing failed, the metrics are all 0
+    pub error: Option<String>,
+}
+
+impl MapModel {
+    /// Evaluates a journey through several waypoints (Mercator). Returns a FeatureCollection of
+    /// the direct and quiet routes, split by leg and LoS, with `JourneyMetrics` in the foreign
+    /// members.
+    pub fn evaluate_multi_point_route(&self, waypoints: &[Coord]) -> Result<String> {
+        let direct_legs = self.route_legs("bicycle_direct", waypoints)?;
+        let quiet_legs = self.route_legs("bicycle_quiet", waypoints)?;
+
+        let mut features = Vec::new();
+        for (kind, legs) in [
+            ("bicycle_direct", &direct_legs),
+            ("quiet_bike", &quiet_legs),
+        ] {
+            for (leg, route) in legs.iter().enumerate() {
+                for (linestring, los) in route.split_linestrings(&self.graph, |r| self.los[r.0]) {
+                    let mut f = self.graph.mercator.to_wgs84_gj(&linestring);
+                    f.set_property("kind", kind);
+                    f.set_property("leg", leg);
+                    f.set_property("los", serde_json::to_value(los)?);
+                    features.push(f);
+                }
+            }
+        }
+
+        let metrics = self.journey_metrics(String::new(), &direct_legs, &quiet_legs);
+        Ok(serde_json::to_string(&FeatureCollection {
+            features,
+            bbox: None,
+            foreign_members: Some(into_object_value(serde_json::to_value(&metrics)?)),
+        })?)
+    }
+
+    /// Evaluates many journeys at once. A journey that can't be routed gets an error, instead of
+    /// failing everything.
+    pub fn evaluate_journeys(
+        &mut self,
+        journeys: Vec<JourneyInput>,
+        timer: &mut Timer,
+    ) -> Vec<JourneyMetrics> {
+        self.recalculate_quiet_router(timer);
+        timer.step(format!("evaluate {} journeys", journeys.len()));
+
+        journeys
+            .into_iter()
+            .map(|journey| {
+                let waypoints: Vec<Coord> = journey
+                    .waypoints
+                    .into_iter()
+                    .map(|pt| self.graph.mercator.pt_to_mercator(pt))
+                    .collect();
+                let result = self
+                    .route_legs("bicycle_direct", &waypoints)
+                    .and_then(|direct| {
+                        let quiet = self.route_legs("bicycle_quiet", &waypoints)?;
+                        Ok((direct, quiet))
+                    });
+                match result {
+                    Ok((direct, quiet)) => self.journey_metrics(journey.id, &direct, &quiet),
+                    Err(err) => JourneyMetrics {
+                        id: journey.id,
+                        direct_length: 0.0,
+                        quiet_length: 0.0,
+                        direct_minutes: 0.0,
+                        quiet_minutes: 0.0,
+                        percent_high_los: 0.0,
+                        max_gradient: 0.0,
+                        error: Some(err.to_string()),
+                    },
+                }
+            })
+            .collect()
+    }
+
+    fn route_legs(&self, profile_name: &str, waypoints: &[Coord]) -> Result<Vec<Route>> {
+        if waypoints.len() < 2 {
+            bail!("A journey needs at least two waypoints");
+        }
+        if profile_name == "bicycle_quiet" {
+            assert!(self.quiet_router_ok);
+        }
+
+        let profile = self.graph.profile_names[profile_name];
+        let mut legs = Vec::new();
+        for pair in waypoints.windows(2) {
+            let start = self.graph.snap_to_road(pair[0], profile);
+            let end = self.graph.snap_to_road(pair[1], profile);
+            legs.push(self.graph.routers[profile.0].route(&self.graph, start, end)?);
+        }
+        Ok(legs)
+    }
+
+    fn journey_metrics(&self, id: String, direct: &[Route], quiet: &[Route]) -> JourneyMetrics {
+        let direct_length = direct
+            .iter()
+            .map(|route| Euclidean.length(&route.linestring(&self.graph)))
+            .sum();
+
+        let mut quiet_length = 0.0;
+        let mut high_los_length = 0.0;
+        let mut max_gradient: f64 = 0.0;
+        for route in quiet {
+            for step in &route.steps {
+                if let PathStep::Road { road, .. } = step {
+                    let length = self.graph.roads[road.0].length_meters;
+                    quiet_length += length;
+                    if self.los[road.0] == LevelOfService::High {
+                        high_los_length += length;
+                    }
+                    max_gradient = max_gradient.max(self.gradients[road.0].abs());
+                }
+            }
+        }
+
+        let minutes = |legs: &[Route]| {
+            legs.iter()
+                .map(|route| self.route_travel_time_minutes(route, RiderType::Standard))
+                .sum()
+        };
+
+        JourneyMetrics {
+            id,
+            direct_length,
+            quiet_length,
+            direct_minutes: minutes(direct),
+            quiet_minutes: minutes(quiet),
+            percent_high_los: if quiet_length > 0.0 {
+                high_los_length / quiet_length
+            } else {
+                0.0
+            },
+            max_gradient,
+            error: None,
+        }
+    }
+}
+
+/// Parses journeys from CSV text with a header row. The `x1`, `y1`, `x2`, and `y2` columns are
+/// required, giving the origin and destination in WGS84. An `id` column is optional, defaulting
+/// to the row number. Other columns are ignored. Errors mention the row number, counting from 1
+/// after the header.
+pub fn parse_journeys_csv(text: &str) -> Result<Vec<JourneyInput>> {
+    let mut reader = csv::ReaderBuilder::new()
+        .trim(csv::Trim::All)
+        .from_reader(text.as_bytes());
+    let headers = reader.headers()?.clone();
+    let find = |name: &str| headers.iter().position(|x| x == name);
+    let id_column = find("id");
+    let mut coord_columns = Vec::new();
+    for name in ["x1", "y1", "x2", "y2"] {
+        let Some(idx) = find(name) else {
+            bail!("CSV is missing a {name} column");
+        };
+        coord_columns.push((name, idx));
+    }
+
+    let mut journeys = Vec::new();
+    for (idx, record) in reader.records().enumerate() {
+        let row = idx + 1;
+        let record = record.map_err(|err| anyhow!("Row {row}: {err}"))?;
+        let mut values = Vec::new();
+        for (name, idx) in &coord_columns {
+            let Some(field) = record.get(*idx) else {
+                bail!("Row {row} is missing {name}");
+            };
+            let Ok(value) = field.parse::<f64>() else {
+                bail!("Row {row} has a bad number for {name}: {field}");
+            };
+            values.push(value);
+        }
+        let id = match id_column.and_then(|idx| record.get(idx)) {
+            Some(id) => id.to_string(),
+            None => row.to_string(),
+        };
+        journeys.push(JourneyInput {
+            id,
+            waypoints: vec![
+                Coord {
+                    x: values[0],
+                    y: values[1],
+                },
+                Coord {
+                    x: values[2],
+                    y: values[3],
+                },
+            ],
+        });
+    }
+    Ok(journeys)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_journeys_csv() {
+        let journeys = parse_journeys_csv(
+            "y1,x1,note,x2,y2\n55.9,-3.2,first,-3.1,55.95\n\n56.0,-3.3,,-3.25,56.1\n",
+        )
+        .unwrap();
+        assert_eq!(journeys.len(), 2);
+        assert_eq!(journeys[0].id, "1");
+        assert_eq!(
+            journeys[0].waypoints,
+            vec![Coord { x: -3.2, y: 55.9 }, Coord { x: -3.1, y: 55.95 }]
+        );
+        assert_eq!(journeys[1].id, "2");
+
+        let journeys = parse_journeys_csv("id,x1,y1,x2,y2\nabc,1,2,3,4").unwrap();
+        assert_eq!(journeys[0].id, "abc");
+
+        // Quoted fields work
+        let journeys = parse_journeys_csv("id,x1,y1,x2,y2\n\"a, b\",1,2,3,4").unwrap();
+        assert_eq!(journeys[0].id, "a, b");
+
+        assert!(parse_journeys_csv("x1,y1,x2\n1,2,3").is_err());
+        let err = parse_journeys_csv("x1,y1,x2,y2\n1,2,3,4\n1,2,three,4").unwrap_err();
+        assert_eq!(err.to_string(), "Row 2 has a bad number for x2: three");
+        let err = parse_journeys_csv("x1,y1,x2,y2\n1,2,3,4\n1,2,3,4\n1,2,3").unwrap_err();
+        assert!(err.to_string().starts_with("Row 3: "));
+    }
+}
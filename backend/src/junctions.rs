@@ -0,0 +1,213 @@
+use anyhow::Result;
+use geojson::FeatureCollection;
+use graph::{IntersectionID, RoadID};
+use serde::Serialize;
+
+use crate::level_of_service::get_level_of_service;
+use crate::reachable::all_crossed_roads;
+use crate::{
+    is_major_junction, CrossingKind, Dir, InfraType, LevelOfService, MapModel, TrafficVolume,
+};
+
+/// One movement along a route through a major junction
+#[derive(Serialize)]
+pub struct JunctionMovement {
+    pub intersection: IntersectionID,
+    pub from: RoadID,
+    pub to: RoadID,
+    pub num_arms: usize,
+    pub num_arterial_arms: usize,
+    /// Arms with motor traffic that have to be crossed for this movement
+    pub num_crossed_arms: usize,
+    /// mph
+    pub max_crossed_speed: Option<usize>,
+    pub max_crossed_traffic: Option<TrafficVolume>,
+    pub crossing: Option<CrossingKind>,
+    pub los: LevelOfService,
+}
+
+impl MapModel {
+    /// Assess every movement along a route that passes through a major junction
+    pub fn assess_route_junctions(&self, route_id: usize) -> Result<Vec<JunctionMovement>> {
+        let Some(route) = self.routes.get(&route_id) else {
+            bail!("No route {route_id}");
+        };
+        Ok(route
+            .roads
+            .windows(2)
+            .filter_map(|pair| self.assess_movement(pair[0], pair[1]))
+            .collect())
+    }
+
+    /// Returns GJ points for every junction movement along every route
+    pub fn get_junction_assessment(&self) -> Result<FeatureCollection> {
+        let mut features = Vec::new();
+        for route_id in self.routes.keys() {
+            for movement in self.assess_route_junctions(*route_id)? {
+                let mut f = self
+                    .graph
+                    .mercator
+                    .to_wgs84_gj(&self.graph.intersections[movement.intersection.0].point);
+                f.set_property("route", *route_id);
+                if let serde_json::Value::Object(props) = serde_json::to_value(&movement)? {
+                    for (key, value) in props {
+                        f.set_property(key, value);
+                    }
+                }
+                features.push(f);
+            }
+        }
+        Ok(FeatureCollection {
+            features,
+            bbox: None,
+            foreign_members: None,
+        })
+    }
+
+    /// Returns (movements at high LoS, all movements) through major junctions along every route.
+    /// Movements between two different routes aren't counted.
+    pub fn count_junction_movements(&self) -> (usize, usize) {
+        let mut high = 0;
+        let mut total = 0;
+        for route in self.routes.values() {
+            for pair in route.roads.windows(2) {
+                if let Some(movement) = self.assess_movement(pair[0], pair[1]) {
+                    total += 1;
+                    if movement.los == LevelOfService::High {
+                        high += 1;
+                    }
+                }
+            }
+        }
+        (high, total)
+    }
+
+    /// None if this movement doesn't pass through a major junction
+    fn assess_movement(
+        &self,
+        (r1, dir1): (RoadID, Dir),
+        (r2, _): (RoadID, Dir),
+    ) -> Option<JunctionMovement> {
+        let road1 = &self.graph.roads[r1.0];
+        let i = match dir1 {
+            Dir::Forwards => road1.dst_i,
+            Dir::Backwards => road1.src_i,
+        };
+        let intersection = &self.graph.intersections[i.0];
+        if !is_major_junction(intersection, &self.highways) {
+            return None;
+        }
+
+        let crossed: Vec<RoadID> = all_crossed_roads(&intersection.roads, r1, r2)
+            .into_iter()
+            .filter(|r| self.highways[r.0].has_motor_vehicles())
+            .collect();
+        let crossing = self.crossings.get(&i).cloned();
+
+        Some(JunctionMovement {
+            intersection: i,
+            from: r1,
+            to: r2,
+            num_arms: intersection.roads.len(),
+            num_arterial_arms: intersection
+                .roads
+                .iter()
+                .filter(|r| self.highways[r.0].is_arterial_road())
+                .count(),
+            num_crossed_arms: crossed.len(),
+            max_crossed_speed: crossed.iter().map(|r| self.speeds[r.0]).max(),
+            max_crossed_traffic: crossed
+                .iter()
+                .map(|r| self.traffic_volumes[r.0])
+                .reduce(|a, b| if b > a { b } else { a }),
+            crossing,
+            los: self.junction_level_of_service(&crossed, crossing),
+        })
+    }
+
+    /// A movement is as bad as the worst arm it crosses, judged like riding in mixed traffic on
+    /// that arm. Crossings make most movements high LoS.
+    fn junction_level_of_service(
+        &self,
+        crossed: &[RoadID],
+        crossing: Option<CrossingKind>,
+    ) -> LevelOfService {
+        if crossed.is_empty() {
+            return LevelOfService::High;
+        }
+        match crossing {
+            Some(CrossingKind::Signalised | CrossingKind::GradeSeparated) => {
+                return LevelOfService::High;
+            }
+            // Uncontrolled crossings are only comfortable over slower, quieter roads
+            Some(CrossingKind::Zebra | CrossingKind::Parallel) => {
+                return crossed
+                    .iter()
+                    .map(|r| uncontrolled_crossing_los(self.speeds[r.0], self.traffic_volumes[r.0]))
+                    .max()
+                    .unwrap();
+            }
+            None => {}
+        }
+
+        crossed
+            .iter()
+            .map(|r| {
+                get_level_of_service(
+                    InfraType::MixedTraffic,
+                    self.speeds[r.0],
+                    self.traffic_volumes[r.0],
+                    self.within_settlement[r.0],
+                )
+            })
+            .max()
+            .unwrap()
+    }
+}
+
+/// The LoS of a zebra or parallel crossing over one road. Drivers are less likely to stop on fast
+/// roads, and there are fewer gaps in busy traffic. The thresholds are made up.
+fn uncontrolled_crossing_los(speed: usize, traffic: TrafficVolume) -> LevelOfService {
+    if speed <= 30 {
+        LevelOfService::High
+    } else if speed <= 40 {
+        if traffic <= TrafficVolume::UpTo4000 {
+            LevelOfService::Medium
+        } else {
+            LevelOfService::Low
+        }
+    } else if traffic <= TrafficVolume::UpTo2000 {
+        LevelOfService::Low
+    } else {
+        LevelOfService::ShouldNotBeUsed
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_uncontrolled_crossing_los() {
+        assert_eq!(
+            uncontrolled_crossing_los(20, TrafficVolume::Over4000),
+            LevelOfService::High
+        );
+        assert_eq!(
+            uncontrolled_crossing_los(40, TrafficVolume::UpTo2000),
+            LevelOfService::Medium
+        );
+        assert_eq!(
+            uncontrolled_crossing_los(40, TrafficVolume::Over4000),
+            LevelOfService::Low
+        );
+        assert_eq!(
+            uncontrolled_crossing_los(60, TrafficVolume::UpTo1000),
+            LevelOfService::Low
+        );
+        assert_eq!(
+            uncontrolled_crossing_los(60, TrafficVolume::Over4000),
+            LevelOfService::ShouldNotBeUsed
+        );
+    }
+}
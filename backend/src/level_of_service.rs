@@ -1,4 +1,4 @@
-use enum_map::Enum;
+use enum_map::{Enum, EnumMap};
 use graph::RoadID;
 use serde::{Deserialize, Serialize};
 
@@ -34,6 +34,21 @@ pub enum TrafficVolume {
 }
 
 impl MapModel {
+    /// Like `LevelOfService::penalty`, but respecting any overrides set for sensitivity analysis
+    pub fn los_penalty(&self, los: LevelOfService) -> f64 {
+        match self.penalty_overrides {
+            Some(ref penalties) => penalties[los],
+            None => los.penalty(),
+        }
+    }
+
+    /// Change the penalties used for quiet routing and weighted directness. None restores the
+    /// defaults.
+    pub fn set_penalty_overrides(&mut self, penalties: Option<EnumMap<LevelOfService, f64>>) {
+        self.penalty_overrides = penalties;
+        self.quiet_router_ok = false;
+    }
+
     pub fn calculate_level_of_service(&self, r: RoadID) -> LevelOfService {
         get_level_of_service(
             self.get_infra_type(r),
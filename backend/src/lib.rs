@@ -3,9 +3,9 @@ extern crate anyhow;
 #[macro_use]
 extern crate log;
 
-use std::collections::HashMap;
+use std::collections::{BTreeMap, HashMap, HashSet};
 
-use enum_map::Enum;
+use enum_map::{Enum, EnumMap};
 use geo::{Area, Coord, MultiPolygon, Point};
 use geojson::GeoJson;
 use graph::{Graph, Intersection, IntersectionID, RoadID, Timer};
@@ -13,22 +13,44 @@ use rstar::{primitives::GeomWithData, RTree};
 use serde::{Deserialize, Serialize};
 use wasm_bindgen::prelude::*;
 
+pub use crate::crossings::CrossingKind;
 pub use crate::existing::Highway;
 pub use crate::level_of_service::{LevelOfService, TrafficVolume};
+pub use crate::route_report::RouteReport;
 use crate::routes::{Dir, InMemoryRoute, SavedRoute, SetRouteInput, Waypoint};
 
+pub mod coherence;
+mod connect_pois;
 mod costs;
+pub mod crossings;
+mod directions;
 mod disconnected;
+mod draft_network;
 mod evaluate;
 pub mod existing;
+mod gaps;
+mod isochrones;
+pub mod journeys;
+mod junctions;
 mod level_of_service;
+mod long_distance;
+mod marginal;
 mod mesh_density;
 pub mod od;
+pub mod optimiser;
 pub mod places;
 mod reachable;
+mod route_report;
 mod route_snapper;
 mod routes;
+mod scenarios;
+pub mod sensitivity;
+mod severances;
 mod stats;
+pub mod targets;
+mod tier_suggestions;
+pub mod travel_time;
+mod unmet_demand;
 mod uptake;
 mod utils;
 mod wasm;
@@ -45,6 +67,27 @@ pub struct MapModel {
     routes: HashMap<usize, InMemoryRoute>,
     #[serde(skip_serializing, skip_deserializing, default)]
     id_counter: usize,
+    /// KPI targets, stored in the savefile alongside routes
+    #[serde(skip_serializing, skip_deserializing, default)]
+    targets: Vec<targets::Target>,
+    /// If set, only roads within this network distance of the network count as reachable. Stored
+    /// in the savefile.
+    #[serde(skip_serializing, skip_deserializing, default)]
+    reachability_limit_meters: Option<f64>,
+    /// Planned crossings at junctions. Stored in the savefile.
+    #[serde(skip_serializing, skip_deserializing, default)]
+    crossings: HashMap<IntersectionID, CrossingKind>,
+    /// The name of the scenario held in `routes`, `id_counter`, and `crossings`. Stored in the
+    /// savefile.
+    #[serde(
+        skip_serializing,
+        skip_deserializing,
+        default = "scenarios::default_scenario_name"
+    )]
+    current_scenario: String,
+    /// Scenarios not being edited right now. Stored in the savefile.
+    #[serde(skip_serializing, skip_deserializing, default)]
+    other_scenarios: BTreeMap<String, scenarios::Scenario>,
 
     boundary_wgs84: MultiPolygon,
 
@@ -79,6 +122,7 @@ pub struct MapModel {
     // Stats calculated on a network only with existing infrastructure imported
     baseline_stats: stats::Stats,
     baseline_slow_stats: od::SlowStats,
+    baseline_coherence: coherence::CoherenceScores,
 
     high_demand_threshold: usize,
     medium_demand_threshold: usize,
@@ -93,6 +137,9 @@ pub struct MapModel {
     tiers: Vec<Option<Tier>>,
     #[serde(skip_serializing, skip_deserializing, default)]
     los: Vec<LevelOfService>,
+    /// Only used for sensitivity analysis
+    #[serde(skip_serializing, skip_deserializing, default)]
+    penalty_overrides: Option<EnumMap<LevelOfService, f64>>,
     #[serde(skip_serializing, skip_deserializing, default)]
     quiet_router_ok: bool,
 }
@@ -110,6 +157,33 @@ pub enum InfraType {
     MixedTrafficWithSpeedVolume,
 }
 
+impl InfraType {
+    /// A rough capital cost in pounds per kilometer. These are placeholder values for comparing
+    /// options, not estimates for a real scheme. Even mixed traffic routes need some signage and
+    /// wayfinding, so nothing is free.
+    pub fn cost_per_km(self) -> f64 {
+        match self {
+            InfraType::Segregated => 1_500_000.0,
+            InfraType::SegregatedWithSpeedVolume => 1_800_000.0,
+            InfraType::OffRoad => 800_000.0,
+            InfraType::SharedFootway => 300_000.0,
+            InfraType::CycleLane => 250_000.0,
+            InfraType::MixedTraffic => 20_000.0,
+            InfraType::MixedTrafficWithSpeedVolume => 400_000.0,
+        }
+    }
+
+    /// The cost in pounds of building this along some length of road. Keeping existing
+    /// infrastructure of the same type is free.
+    pub fn build_cost(self, length_meters: f64, existing: Option<InfraType>) -> f64 {
+        if existing == Some(self) {
+            0.0
+        } else {
+            length_meters / 1000.0 * self.cost_per_km()
+        }
+    }
+}
+
 #[derive(
     Clone, Copy, Debug, PartialEq, Eq, PartialOrd, Ord, Hash, Enum, Serialize, Deserialize,
 )]
@@ -196,6 +270,11 @@ impl MapModel {
             closest_intersection_major,
             routes: HashMap::new(),
             id_counter: 0,
+            targets: Vec::new(),
+            reachability_limit_meters: None,
+            crossings: HashMap::new(),
+            current_scenario: scenarios::default_scenario_name(),
+            other_scenarios: BTreeMap::new(),
             boundary_wgs84,
             commute_desire_lines,
             other_desire_lines,
@@ -219,12 +298,14 @@ impl MapModel {
             // Calculated below
             baseline_stats: stats::Stats::default(),
             baseline_slow_stats: od::SlowStats::default(),
+            baseline_coherence: coherence::CoherenceScores::default(),
             high_demand_threshold: 0,
             medium_demand_threshold: 0,
             infra_types,
             override_infra_type,
             tiers,
             los,
+            penalty_overrides: None,
             quiet_router_ok: false,
         };
 
@@ -234,6 +315,7 @@ impl MapModel {
         model.baseline_stats = model.get_stats();
         model.recalculate_quiet_router(timer);
         model.baseline_slow_stats = model.get_slow_stats(timer);
+        model.baseline_coherence = model.get_coherence_scores(Some(&model.baseline_slow_stats));
         // Clear those edits
         model.clear_all_routes();
         model.recalculate_quiet_router(timer);
@@ -249,6 +331,10 @@ impl MapModel {
         &self.baseline_stats
     }
 
+    pub fn get_baseline_coherence(&self) -> &coherence::CoherenceScores {
+        &self.baseline_coherence
+    }
+
     pub fn recalculate_after_edits(&mut self) {
         self.infra_types = std::iter::repeat(None)
             .take(self.graph.roads.len())
@@ -397,8 +483,35 @@ impl MapModel {
             true
         }
     }
+
+    /// The roads of every POI of one kind, in the same order used by the UI
+    pub fn get_pois_of_kind(&self, kind: &str) -> Result<Vec<HashSet<RoadID>>> {
+        Ok(match kind {
+            "schools" => self.schools.iter().map(|x| [x.road].into()).collect(),
+            "gp_hospitals" => self.gp_hospitals.iter().map(|x| [x.road].into()).collect(),
+            "railway_stations" => self
+                .railway_stations
+                .iter()
+                .map(|x| [x.road].into())
+                .collect(),
+            "greenspaces" => self.greenspaces.iter().map(|x| x.roads.clone()).collect(),
+            "town_centres" => self.town_centres.iter().map(|x| x.roads.clone()).collect(),
+            "settlements" => self.settlements.iter().map(|x| x.roads.clone()).collect(),
+            _ => bail!("Unknown POI kind {kind}"),
+        })
+    }
 }
 
+/// Every kind understood by `get_pois_of_kind`
+pub const POI_KINDS: [&str; 6] = [
+    "schools",
+    "gp_hospitals",
+    "railway_stations",
+    "greenspaces",
+    "town_centres",
+    "settlements",
+];
+
 #[derive(Serialize)]
 pub struct DynamicRoad {
     id: usize,
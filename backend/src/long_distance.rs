@@ -0,0 +1,133 @@
+use std::collections::{BTreeSet, HashMap};
+
+use anyhow::Result;
+use geo::{Centroid, Distance, Euclidean, Haversine, Length};
+use geojson::{Feature, FeatureCollection, Geometry};
+use graph::{PathStep, RoadID};
+use utils::{collapse_degree_2, KeyedLineString};
+
+use crate::reachable::route_input_from_roads;
+use crate::routes::{fix_dir, glue_route_wgs84};
+use crate::{utils::into_object_value, Dir, MapModel, Tier};
+
+impl MapModel {
+    /// Route between every pair of settlements within `max_distance_meters` (straight-line) using
+    /// the quiet router. The parts of those routes outside settlements are merged into corridors,
+    /// returned as FeatureCollection<LineString, SetRouteInput & { priority, length_meters,
+    /// settlement_pairs }>, sorted by priority. The priority of a pair of settlements is a
+    /// gravity model: the product of populations divided by the squared distance. A corridor's
+    /// priority is the highest total priority of the pairs using any of its roads.
+    pub fn generate_long_distance_network(&self, max_distance_meters: f64) -> Result<String> {
+        assert!(self.quiet_router_ok);
+        let profile = self.graph.profile_names["bicycle_quiet"];
+
+        // Priority and names, per pair of settlements
+        let mut pairs: Vec<(f64, String)> = Vec::new();
+        // Per road, the pairs using it
+        let mut road_pairs: HashMap<RoadID, BTreeSet<usize>> = HashMap::new();
+        for (idx1, s1) in self.settlements.iter().enumerate() {
+            for (idx2, s2) in self.settlements.iter().enumerate() {
+                // Routes are bidirectional, so just check one direction
+                if idx1 >= idx2 {
+                    continue;
+                }
+
+                let centroid1 = s1.polygon.centroid().unwrap();
+                let centroid2 = s2.polygon.centroid().unwrap();
+                let dist = Euclidean.distance(centroid1, centroid2);
+                if dist > max_distance_meters {
+                    continue;
+                }
+
+                let start = self.graph.snap_to_road(centroid1.into(), profile);
+                let end = self.graph.snap_to_road(centroid2.into(), profile);
+                let Ok(route) = self.graph.routers[profile.0].route(&self.graph, start, end) else {
+                    continue;
+                };
+
+                let pair = pairs.len();
+                pairs.push((
+                    (s1.population * s2.population) as f64 / dist.max(1.0).powi(2),
+                    format!(
+                        "{} - {}",
+                        s1.name.as_deref().unwrap_or("unnamed settlement"),
+                        s2.name.as_deref().unwrap_or("unnamed settlement")
+                    ),
+                ));
+                for step in route.steps {
+                    if let PathStep::Road { road, .. } = step {
+                        if !self.within_settlement[road.0] {
+                            road_pairs.entry(road).or_default().insert(pair);
+                        }
+                    }
+                }
+            }
+        }
+        info!(
+            "Routed between {} pairs of settlements, using {} roads outside settlements",
+            pairs.len(),
+            road_pairs.len()
+        );
+
+        // Overlapping routes only produce one corridor
+        let pieces = road_pairs
+            .keys()
+            .map(|r| KeyedLineString {
+                linestring: self.graph.roads[r.0].linestring.clone(),
+                ids: vec![(*r, true)],
+                key: (),
+            })
+            .collect();
+
+        let mut corridors = Vec::new();
+        for line in collapse_degree_2(pieces) {
+            let roads: Vec<(RoadID, Dir)> = line.ids.into_iter().map(fix_dir).collect();
+            let mut corridor_pairs = BTreeSet::new();
+            let mut priority: f64 = 0.0;
+            for (r, _) in &roads {
+                let pairs_here = &road_pairs[r];
+                priority = priority.max(pairs_here.iter().map(|p| pairs[*p].0).sum());
+                corridor_pairs.extend(pairs_here.iter().cloned());
+            }
+            corridors.push((priority, roads, corridor_pairs));
+        }
+        corridors.sort_by(|a, b| b.0.total_cmp(&a.0));
+
+        let mut features = Vec::new();
+        for (priority, roads, corridor_pairs) in corridors {
+            let linestring_wgs84 = glue_route_wgs84(&self.graph, &roads);
+            let name = roads
+                .iter()
+                .filter_map(|(r, _)| self.graph.roads[r.0].osm_tags.get("name").cloned())
+                .next()
+                .unwrap_or_else(String::new);
+
+            let mut f = Feature::from(Geometry::from(&linestring_wgs84));
+            f.properties = Some(into_object_value(serde_json::to_value(
+                route_input_from_roads(
+                    &self.graph,
+                    &roads,
+                    name,
+                    "generated long-distance corridor".to_string(),
+                    Tier::LongDistance,
+                ),
+            )?));
+            f.set_property("priority", priority);
+            f.set_property("length_meters", Haversine.length(&linestring_wgs84));
+            f.set_property(
+                "settlement_pairs",
+                corridor_pairs
+                    .into_iter()
+                    .map(|p| pairs[p].1.clone())
+                    .collect::<Vec<_>>(),
+            );
+            features.push(f);
+        }
+
+        Ok(serde_json::to_string(&FeatureCollection {
+            features,
+            bbox: None,
+            foreign_members: None,
+        })?)
+    }
+}
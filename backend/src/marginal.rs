@@ -0,0 +1,121 @@
+use std::collections::BTreeMap;
+
+use anyhow::Result;
+use graph::Timer;
+use serde::Serialize;
+
+use crate::targets::{metric_values, MetricValues};
+use crate::MapModel;
+
+/// The `Stats` fields compared with and without each route
+const KEY_METRICS: [&str; 11] = [
+    "percent_reachable_population",
+    "percent_reachable_imd_population",
+    "percent_reachable_schools",
+    "percent_reachable_gp_hospitals",
+    "percent_reachable_railway_stations",
+    "percent_reachable_greenspaces",
+    "percent_reachable_town_centres",
+    "percent_reachable_settlements",
+    "covered_high_demand",
+    "covered_medium_demand",
+    "high_los_primary_secondary_length",
+];
+
+/// The share of OD trips by LoS, only calculated when requested. Routes can be ranked by the
+/// first.
+const OD_LOS_METRICS: [(&str, &str); 4] = [
+    ("High", "od_percent_high_los"),
+    ("Medium", "od_percent_medium_los"),
+    ("Low", "od_percent_low_los"),
+    ("ShouldNotBeUsed", "od_percent_should_not_be_used_los"),
+];
+
+/// How much worse things would be without one route
+#[derive(Serialize)]
+pub struct RouteContribution {
+    pub id: usize,
+    pub name: String,
+    pub length_meters: f64,
+    /// For every key metric, the current value minus the value without this route
+    pub changes: BTreeMap<String, f64>,
+    /// The change in the metric used for ranking
+    pub benefit: f64,
+    pub benefit_per_km: f64,
+}
+
+impl MapModel {
+    /// For every route, remove it alone and see how much key stats change. Returns routes ranked
+    /// by the benefit per kilometer in `metric`, which must be one of `KEY_METRICS`, or
+    /// `od_percent_high_los` when `include_od` is set. Calculating OD changes is very slow, but
+    /// reports the change in every LoS. Routes with no length get a `benefit_per_km` of 0.
+    pub fn rank_route_contributions(
+        &mut self,
+        metric: &str,
+        include_od: bool,
+        timer: &mut Timer,
+    ) -> Result<Vec<RouteContribution>> {
+        if !KEY_METRICS.contains(&metric) && !(include_od && metric == OD_LOS_METRICS[0].1) {
+            bail!("Can't rank routes by {metric}");
+        }
+
+        timer.step("calculate current stats");
+        let current = self.marginal_metrics(include_od, timer)?;
+
+        let mut ids: Vec<usize> = self.routes.keys().cloned().collect();
+        ids.sort();
+        let mut results = Vec::new();
+        for id in ids {
+            timer.step(format!("calculate stats without route {id}"));
+            let without =
+                self.with_route_removed(id, |model| model.marginal_metrics(include_od, timer))??;
+
+            let mut changes = BTreeMap::new();
+            for (key, value) in &current {
+                let before = without.get(key).and_then(|x| x.as_f64()).unwrap_or(0.0);
+                changes.insert(key.clone(), value.as_f64().unwrap_or(0.0) - before);
+            }
+
+            let route = &self.routes[&id];
+            let length_meters: f64 = route
+                .roads
+                .iter()
+                .map(|(r, _)| self.graph.roads[r.0].length_meters)
+                .sum();
+            let benefit = changes[metric];
+            results.push(RouteContribution {
+                id,
+                name: route.name.clone(),
+                length_meters,
+                changes,
+                benefit,
+                benefit_per_km: if length_meters > 0.0 {
+                    benefit / (length_meters / 1000.0)
+                } else {
+                    0.0
+                },
+            });
+        }
+
+        results.sort_by(|a, b| b.benefit_per_km.total_cmp(&a.benefit_per_km));
+        Ok(results)
+    }
+
+    fn marginal_metrics(&mut self, include_od: bool, timer: &mut Timer) -> Result<MetricValues> {
+        let mut values = metric_values(&self.get_stats(), None)?;
+        values.retain(|key, _| KEY_METRICS.contains(&key.as_str()));
+
+        if include_od {
+            self.recalculate_quiet_router(timer);
+            let fast_sample = true;
+            let od = self.od_counts(fast_sample, "bicycle_quiet")?;
+            let mut out = serde_json::Map::new();
+            od.describe(self, &mut out)?;
+            for (los, key) in OD_LOS_METRICS {
+                let percent = out["od_percents_los"][los].as_f64().unwrap_or(0.0);
+                values.insert(key.to_string(), percent.into());
+            }
+        }
+        Ok(values)
+    }
+}
@@ -9,13 +9,16 @@ use nanorand::WyRand;
 use serde::{Deserialize, Serialize};
 
 use crate::{
-    stats::percent, uptake, utils::into_object_value, InfraType, LevelOfService, MapModel, Tier,
+    stats::percent, travel_time::RiderType, uptake, utils::into_object_value, Dir, InfraType,
+    LevelOfService, MapModel, Tier,
 };
 
 pub struct CountsOD {
     pub counts: HashMap<RoadID, usize>,
     pub succeeded: usize,
     pub failed: usize,
+    /// The average journey time for a standard rider, weighted by uptake
+    pub average_minutes: f64,
 }
 
 #[derive(Default, Serialize, Deserialize)]
@@ -26,7 +29,8 @@ pub struct SlowStats {
 }
 
 impl CountsOD {
-    /// Populate `out` with `od_percents_los`, `od_percents_infra_type`, and `od_percents_tier`
+    /// Populate `out` with `od_percents_los`, `od_percents_infra_type`, `od_percents_tier`, and
+    /// `od_average_minutes`
     pub fn describe(
         self,
         map: &MapModel,
@@ -87,6 +91,10 @@ impl CountsOD {
             "od_percents_los".to_string(),
             serde_json::Value::Object(od_percents_los),
         );
+        out.insert(
+            "od_average_minutes".to_string(),
+            self.average_minutes.into(),
+        );
 
         Ok(())
     }
@@ -110,6 +118,7 @@ impl MapModel {
             requests.len()
         );
         let mut total_uptake = 0.0;
+        let mut total_minutes = 0.0;
 
         for (pt1, pt2, uptake_multiplier) in requests {
             let start = self.graph.snap_to_road(pt1, profile);
@@ -129,6 +138,7 @@ impl MapModel {
 
             let count = uptake::pct_godutch_2020(route_length) * uptake_multiplier;
             total_uptake += count;
+            total_minutes += count * self.route_travel_time_minutes(&route, RiderType::Standard);
             for step in route.steps {
                 if let PathStep::Road { road, .. } = step {
                     *counts.entry(road).or_insert(0.0) += count;
@@ -146,6 +156,11 @@ impl MapModel {
                 .collect(),
             succeeded,
             failed,
+            average_minutes: if total_uptake > 0.0 {
+                total_minutes / total_uptake
+            } else {
+                0.0
+            },
         })
     }
 
@@ -396,12 +411,23 @@ fn find_cycling_demand_thresholds(demands: &Vec<usize>) -> Result<(usize, usize)
     Ok((high, medium))
 }
 
-// The result is distance, weighted by the per-LoS penalty
+// The result is distance, weighted by the per-LoS penalty, plus the cost of movements through
+// major junctions
 fn weighted_route_length(model: &MapModel, route: &Route) -> f64 {
     let mut cost = 0.0;
+    let mut prev: Option<(RoadID, Dir)> = None;
     for step in &route.steps {
-        if let PathStep::Road { road, .. } = step {
-            cost += model.los[road.0].penalty() * model.graph.roads[road.0].length_meters;
+        if let PathStep::Road { road, forwards } = step {
+            cost += model.los_penalty(model.los[road.0]) * model.graph.roads[road.0].length_meters;
+            let dir = if *forwards {
+                Dir::Forwards
+            } else {
+                Dir::Backwards
+            };
+            if let Some(prev) = prev {
+                cost += model.junction_cost_meters(prev, (*road, dir));
+            }
+            prev = Some((*road, dir));
         }
     }
     cost
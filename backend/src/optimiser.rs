@@ -0,0 +1,294 @@
+use std::collections::BTreeMap;
+
+use anyhow::Result;
+use graph::RoadID;
+use serde::{Deserialize, Serialize};
+
+use crate::existing::classify_existing_osm_infra;
+use crate::level_of_service::{get_level_of_service, TrafficVolume};
+use crate::{InfraType, LevelOfService, MapModel};
+
+/// What the optimiser tries to maximise
+#[derive(Clone, Copy, PartialEq, Deserialize)]
+pub enum Objective {
+    /// The length of the network with high LoS
+    HighLosLength,
+    /// The number of sampled OD trips crossing high LoS roads. This assumes the routes people
+    /// take don't change as infrastructure changes.
+    OdHighLos,
+}
+
+#[derive(Serialize)]
+pub struct OptimiserResult {
+    pub budget: f64,
+    pub total_cost: f64,
+    /// The recommended changes, only for routes where the infrastructure type differs
+    pub changes: Vec<InfraChange>,
+    /// Route IDs per new infrastructure type, ready to pass to `change_infra_type`
+    pub changes_by_infra_type: BTreeMap<String, Vec<usize>>,
+    pub high_los_length_before: f64,
+    pub high_los_length_after: f64,
+    /// Only calculated for `Objective::OdHighLos`. This is a demand-weighted road share, not a
+    /// share of trips: every sampled OD trip counts once per road it uses, and this is the
+    /// fraction of those counts on high LoS route roads, from 0 to 1.
+    pub od_demand_share_high_los_before: Option<f64>,
+    pub od_demand_share_high_los_after: Option<f64>,
+}
+
+#[derive(Serialize)]
+pub struct InfraChange {
+    pub route: usize,
+    pub from: InfraType,
+    pub to: InfraType,
+    pub cost: f64,
+}
+
+/// One road in a route section, with everything needed to evaluate choices for it
+struct SectionRoad {
+    length_meters: f64,
+    /// How much high LoS on this road counts towards the objective
+    weight: f64,
+    speed: usize,
+    traffic: TrafficVolume,
+    within_settlement: bool,
+    /// Infrastructure already built, according to OSM
+    existing: Option<InfraType>,
+}
+
+/// One choice of infrastructure for one route section
+#[derive(Clone, Copy)]
+struct Choice {
+    infra_type: InfraType,
+    cost: f64,
+    high_los_length: f64,
+    value: f64,
+}
+
+impl MapModel {
+    /// Choose an infrastructure type per route section (each route is one section, with uniform
+    /// LoS and fit) to maximise the objective, with a total cost (in pounds, using placeholder
+    /// costs) under the budget. Roads that already have some infrastructure cost nothing to keep
+    /// as they are, and no choice may lower their LoS. Every section starts with the cheapest
+    /// type that fits, then the upgrade with the best gain per pound is repeatedly picked. This is
+    /// the usual greedy heuristic for a multiple-choice knapsack problem, so the answer isn't
+    /// always optimal. The model isn't changed.
+    pub fn optimise_infra_types(
+        &self,
+        budget: f64,
+        objective: Objective,
+    ) -> Result<OptimiserResult> {
+        let od_counts = if objective == Objective::OdHighLos {
+            let fast_sample = true;
+            Some(self.od_counts(fast_sample, "bicycle_quiet")?.counts)
+        } else {
+            None
+        };
+        let weight = |r: RoadID| match od_counts {
+            Some(ref counts) => counts.get(&r).cloned().unwrap_or(0) as f64,
+            None => self.graph.roads[r.0].length_meters,
+        };
+
+        let mut ids: Vec<usize> = self.routes.keys().cloned().collect();
+        ids.sort();
+        let mut options_per_route: Vec<Vec<Choice>> = Vec::new();
+        let mut current_per_route: Vec<Choice> = Vec::new();
+        for id in &ids {
+            let roads: Vec<RoadID> = self.routes[id].roads.iter().map(|(r, _)| *r).collect();
+            let section: Vec<SectionRoad> = roads
+                .iter()
+                .map(|r| SectionRoad {
+                    length_meters: self.graph.roads[r.0].length_meters,
+                    weight: weight(*r),
+                    speed: self.speeds[r.0],
+                    traffic: self.traffic_volumes[r.0],
+                    within_settlement: self.within_settlement[r.0],
+                    existing: classify_existing_osm_infra(
+                        self.is_offroad[r.0],
+                        &self.graph.roads[r.0].osm_tags,
+                    ),
+                })
+                .collect();
+
+            let mut choices: Vec<Choice> = candidate_infra_types(self, &roads)
+                .into_iter()
+                .filter(|infra_type| {
+                    roads
+                        .iter()
+                        .all(|r| self.does_infra_type_fit(*r, *infra_type))
+                })
+                .filter_map(|infra_type| evaluate_section(infra_type, &section))
+                .collect();
+            choices.sort_by(|a, b| a.cost.total_cmp(&b.cost));
+            if choices.is_empty() {
+                bail!("No infrastructure type fits route {id} without downgrading what exists");
+            }
+            // The current type might downgrade existing infrastructure, but still report it
+            current_per_route.push(evaluate_choice(self.routes[id].infra_type, &section));
+            options_per_route.push(choices);
+        }
+
+        // Start with the cheapest option everywhere
+        let mut chosen: Vec<Choice> = options_per_route.iter().map(|x| x[0]).collect();
+        let mut total_cost: f64 = chosen.iter().map(|x| x.cost).sum();
+        if total_cost > budget {
+            bail!("The budget can't even cover the cheapest option for every route");
+        }
+
+        loop {
+            // Find the upgrade with the best marginal value per pound that still fits
+            let mut best: Option<(usize, Choice, f64)> = None;
+            for (idx, options) in options_per_route.iter().enumerate() {
+                for option in options {
+                    let extra_cost = option.cost - chosen[idx].cost;
+                    let extra_value = option.value - chosen[idx].value;
+                    if extra_value <= 0.0 || total_cost + extra_cost > budget {
+                        continue;
+                    }
+                    // Free upgrades are always worth it
+                    let ratio = extra_value / extra_cost.max(1.0);
+                    let better = match best {
+                        Some((_, _, x)) => ratio > x,
+                        None => true,
+                    };
+                    if better {
+                        best = Some((idx, *option, ratio));
+                    }
+                }
+            }
+            let Some((idx, option, _)) = best else {
+                break;
+            };
+            total_cost += option.cost - chosen[idx].cost;
+            chosen[idx] = option;
+        }
+
+        let mut changes = Vec::new();
+        let mut changes_by_infra_type: BTreeMap<String, Vec<usize>> = BTreeMap::new();
+        for (idx, id) in ids.iter().enumerate() {
+            let from = self.routes[id].infra_type;
+            let to = chosen[idx].infra_type;
+            if from != to {
+                changes.push(InfraChange {
+                    route: *id,
+                    from,
+                    to,
+                    cost: chosen[idx].cost,
+                });
+                changes_by_infra_type
+                    .entry(format!("{to:?}"))
+                    .or_default()
+                    .push(*id);
+            }
+        }
+
+        let total_od = od_counts
+            .as_ref()
+            .map(|counts| counts.values().sum::<usize>() as f64);
+        let od_demand_share = |choices: &[Choice]| {
+            total_od.map(|total| {
+                let high: f64 = choices.iter().map(|x| x.value).sum();
+                if total == 0.0 {
+                    0.0
+                } else {
+                    high / total
+                }
+            })
+        };
+
+        Ok(OptimiserResult {
+            budget,
+            total_cost,
+            changes,
+            changes_by_infra_type,
+            high_los_length_before: current_per_route.iter().map(|x| x.high_los_length).sum(),
+            high_los_length_after: chosen.iter().map(|x| x.high_los_length).sum(),
+            od_demand_share_high_los_before: od_demand_share(&current_per_route),
+            od_demand_share_high_los_after: od_demand_share(&chosen),
+        })
+    }
+}
+
+/// The cost and value of using one infrastructure type along a whole section, or None if it
+/// would lower the LoS of any road with existing infrastructure
+fn evaluate_section(infra_type: InfraType, section: &[SectionRoad]) -> Option<Choice> {
+    for road in section {
+        if let Some(existing) = road.existing {
+            let los = |infra_type| {
+                get_level_of_service(infra_type, road.speed, road.traffic, road.within_settlement)
+            };
+            // High is the smallest LoS
+            if los(infra_type) > los(existing) {
+                return None;
+            }
+        }
+    }
+    Some(evaluate_choice(infra_type, section))
+}
+
+fn evaluate_choice(infra_type: InfraType, section: &[SectionRoad]) -> Choice {
+    let mut choice = Choice {
+        infra_type,
+        cost: 0.0,
+        high_los_length: 0.0,
+        value: 0.0,
+    };
+    for road in section {
+        choice.cost += infra_type.build_cost(road.length_meters, road.existing);
+        if get_level_of_service(infra_type, road.speed, road.traffic, road.within_settlement)
+            == LevelOfService::High
+        {
+            choice.high_los_length += road.length_meters;
+            choice.value += road.weight;
+        }
+    }
+    choice
+}
+
+/// Off-road routes can only be off-road, and nothing else can be
+fn candidate_infra_types(model: &MapModel, roads: &[RoadID]) -> Vec<InfraType> {
+    if roads.iter().all(|r| model.is_offroad[r.0]) {
+        return vec![InfraType::OffRoad];
+    }
+    vec![
+        InfraType::MixedTraffic,
+        InfraType::MixedTrafficWithSpeedVolume,
+        InfraType::CycleLane,
+        InfraType::SharedFootway,
+        InfraType::Segregated,
+        InfraType::SegregatedWithSpeedVolume,
+    ]
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_existing_infra() {
+        // A busy road with a segregated track already on half of it
+        let road = |existing| SectionRoad {
+            length_meters: 500.0,
+            weight: 500.0,
+            speed: 30,
+            traffic: TrafficVolume::Over4000,
+            within_settlement: true,
+            existing,
+        };
+        let section = vec![road(Some(InfraType::Segregated)), road(None)];
+
+        // Only the new half costs anything
+        let segregated = evaluate_section(InfraType::Segregated, &section).unwrap();
+        assert_eq!(segregated.cost, 0.5 * InfraType::Segregated.cost_per_km());
+        assert_eq!(segregated.high_los_length, 1000.0);
+
+        // Cheaper options would downgrade the existing track
+        assert!(evaluate_section(InfraType::MixedTraffic, &section).is_none());
+        assert!(evaluate_section(InfraType::CycleLane, &section).is_none());
+        // Upgrades are fine, but the existing half isn't free anymore
+        let upgrade = evaluate_section(InfraType::SegregatedWithSpeedVolume, &section).unwrap();
+        assert_eq!(
+            upgrade.cost,
+            InfraType::SegregatedWithSpeedVolume.cost_per_km()
+        );
+    }
+}
@@ -3,7 +3,7 @@ use std::collections::{BinaryHeap, HashMap, HashSet};
 use anyhow::Result;
 use geo::{Haversine, Length};
 use geojson::{Feature, FeatureCollection, Geometry};
-use graph::{Graph, RoadID};
+use graph::{Graph, IntersectionID, RoadID};
 use utils::PriorityQueueItem;
 
 use crate::route_snapper::roads_to_waypoints;
@@ -16,6 +16,9 @@ pub struct Reachability {
     pub network: HashSet<RoadID>,
     pub severances: HashSet<RoadID>,
     pub reachable: HashSet<RoadID>,
+    /// For every reachable road, the distance in meters along High LoS roads from the nearest
+    /// part of the network to the start of this road
+    pub distances: HashMap<RoadID, f64>,
 }
 
 impl Reachability {
@@ -26,16 +29,38 @@ impl Reachability {
     pub fn covers_any(&self, roads: &HashSet<RoadID>) -> bool {
         !self.network.is_disjoint(roads) || !self.reachable.is_disjoint(roads)
     }
+
+    /// How far from the network is this road? 0 if it's part of the network, None if it's not
+    /// reachable.
+    pub fn distance(&self, r: RoadID) -> Option<f64> {
+        if self.network.contains(&r) {
+            Some(0.0)
+        } else {
+            self.distances.get(&r).cloned()
+        }
+    }
+
+    /// The distance to the closest of these roads
+    pub fn min_distance(&self, roads: &HashSet<RoadID>) -> Option<f64> {
+        roads
+            .iter()
+            .filter_map(|r| self.distance(*r))
+            .min_by(|a, b| a.total_cmp(b))
+    }
 }
 
 impl MapModel {
+    /// Flood from the network along High LoS roads. If `reachability_limit_meters` is set, roads
+    /// further than this from the network don't count as reachable.
     pub fn get_reachable_network(&self) -> Reachability {
         let mut network: HashSet<RoadID> = HashSet::new();
         let mut severances: HashSet<RoadID> = HashSet::new();
         let mut reachable: HashSet<RoadID> = HashSet::new();
+        let mut distances: HashMap<RoadID, f64> = HashMap::new();
 
         let mut visited: HashSet<RoadID> = HashSet::new();
-        let mut queue: Vec<RoadID> = Vec::new();
+        let mut queue: BinaryHeap<PriorityQueueItem<usize, RoadID>> = BinaryHeap::new();
+        let limit = self.reachability_limit_meters.map(meters);
 
         for idx in 0..self.graph.roads.len() {
             let id = RoadID(idx);
@@ -50,31 +75,56 @@ impl MapModel {
 
             if self.infra_types[idx].is_some() {
                 network.insert(id);
-                queue.push(id);
+                queue.push(PriorityQueueItem::new(0, id));
             }
         }
 
-        // Flood, avoiding severances
-        while let Some(r) = queue.pop() {
+        // Flood, avoiding severances. Track the distance to the start of each road, like
+        // debug_reachable_path.
+        while let Some(item) = queue.pop() {
+            let r = item.value;
             if visited.contains(&r) {
                 continue;
             }
             visited.insert(r);
 
-            if !network.contains(&r) {
+            // Moving along the network itself is free
+            let cost_after = if network.contains(&r) {
+                item.cost
+            } else {
                 reachable.insert(r);
-            }
+                distances.insert(r, (item.cost as f64) / 100.0);
+                item.cost + meters(self.graph.roads[r.0].length_meters)
+            };
 
-            queue.extend(self.next_reachable_roads(r));
+            for r2 in self.next_reachable_roads(r) {
+                if visited.contains(&r2) || limit.is_some_and(|limit| cost_after > limit) {
+                    continue;
+                }
+                queue.push(PriorityQueueItem::new(cost_after, r2));
+            }
         }
 
         Reachability {
             network,
             severances,
             reachable,
+            distances,
         }
     }
 
+    /// Only roads within this many meters of the network count as reachable. None means no limit.
+    /// The limit must be a finite, non-negative number.
+    pub fn set_reachability_limit(&mut self, limit: Option<f64>) -> Result<()> {
+        if let Some(meters) = limit {
+            if !meters.is_finite() || meters < 0.0 {
+                bail!("Reachability limit must be a non-negative number of meters, not {meters}");
+            }
+        }
+        self.reachability_limit_meters = limit;
+        Ok(())
+    }
+
     /// Show the shortest distance path from any of the start roads to any part of the network. If
     /// any of the start_roads are on the network, then returns an error.
     pub fn debug_reachable_path(&self, start_roads: HashSet<RoadID>) -> Result<String> {
@@ -215,19 +265,17 @@ impl MapModel {
                 }
 
                 let roads = roads_to_steps(&self.graph, roads_in_order)?;
-                let waypoints_wgs84 = roads_to_waypoints(&self.graph, &roads);
                 let linestring_wgs84 = glue_route_wgs84(&self.graph, &roads);
                 let mut f = Feature::from(Geometry::from(&linestring_wgs84));
-                f.properties = Some(into_object_value(serde_json::to_value(&SetRouteInput {
-                    waypoints: waypoints_wgs84,
-
-                    name: "connection to local POI".to_string(),
-                    notes: String::new(),
-                    // Doesn't matter
-                    infra_type: InfraType::MixedTraffic,
-                    override_infra_type: false,
-                    tier: Tier::LocalAccess,
-                })?));
+                f.properties = Some(into_object_value(serde_json::to_value(
+                    route_input_from_roads(
+                        &self.graph,
+                        &roads,
+                        "connection to local POI".to_string(),
+                        String::new(),
+                        Tier::LocalAccess,
+                    ),
+                )?));
                 f.set_property("length_meters", Haversine.length(&linestring_wgs84));
                 return Ok(serde_json::to_string(&f)?);
             }
@@ -255,26 +303,39 @@ impl MapModel {
     /// From a road, find all possible next roads that're reachable. This avoids crossing
     /// perpendicular over anything that isn't high LoS.
     fn next_reachable_roads(&self, r1: RoadID) -> Vec<RoadID> {
+        self.next_reachable_roads_with(
+            r1,
+            |r| self.los[r.0] == LevelOfService::High,
+            |i| self.has_crossing(i),
+        )
+    }
+
+    /// Like `next_reachable_roads`, but lets the caller decide which roads count as high LoS and
+    /// which intersections have a safe crossing, for testing hypothetical upgrades.
+    pub(crate) fn next_reachable_roads_with<
+        F: Fn(RoadID) -> bool,
+        G: Fn(IntersectionID) -> bool,
+    >(
+        &self,
+        r1: RoadID,
+        is_high_los: F,
+        has_crossing: G,
+    ) -> Vec<RoadID> {
         let road1 = &self.graph.roads[r1.0];
         let mut results = Vec::new();
         for i in [road1.src_i, road1.dst_i] {
             let roads_clockwise = &self.graph.intersections[i.0].roads;
+            let crossing = has_crossing(i);
             for r2 in roads_clockwise {
                 if *r2 == r1 {
                     continue;
                 }
 
-                // If both r1 and r2 explicitly have infrastructure, then assume there's a crossing
-                if self.infra_types[r1.0].is_some() && self.infra_types[r2.0].is_some() {
-                    results.push(*r2);
-                    continue;
-                }
-
-                // Otherwise, check what roads we need to cross. If any of them aren't high LoS,
-                // don't allow this movement.
-                if all_crossed_roads(roads_clockwise, r1, *r2)
+                // If any of the roads we need to cross aren't high LoS, don't allow this movement
+                if self
+                    .unprotected_crossed_roads(i, r1, *r2, crossing)
                     .into_iter()
-                    .all(|r| self.los[r.0] == LevelOfService::High)
+                    .all(&is_high_los)
                 {
                     results.push(*r2);
                 }
@@ -282,14 +343,51 @@ impl MapModel {
         }
         results
     }
+
+    /// The roads crossed when moving from r1 to r2 through intersection i, without any crossing.
+    /// If there's a known crossing, or both r1 and r2 explicitly have infrastructure (and so we
+    /// assume there's a crossing), nothing is crossed unprotected.
+    pub(crate) fn unprotected_crossed_roads(
+        &self,
+        i: IntersectionID,
+        r1: RoadID,
+        r2: RoadID,
+        has_crossing: bool,
+    ) -> Vec<RoadID> {
+        if has_crossing || (self.infra_types[r1.0].is_some() && self.infra_types[r2.0].is_some()) {
+            return Vec::new();
+        }
+        all_crossed_roads(&self.graph.intersections[i.0].roads, r1, r2)
+    }
+}
+
+/// Describes a proposed route along some roads, with WGS84 waypoints. The infrastructure type is
+/// a placeholder.
+pub(crate) fn route_input_from_roads(
+    graph: &Graph,
+    roads: &[(RoadID, Dir)],
+    name: String,
+    notes: String,
+    tier: Tier,
+) -> SetRouteInput {
+    SetRouteInput {
+        waypoints: roads_to_waypoints(graph, roads),
+
+        name,
+        notes,
+        // Doesn't matter
+        infra_type: InfraType::MixedTraffic,
+        override_infra_type: false,
+        tier,
+    }
 }
 
 // to cm
-fn meters(x: f64) -> usize {
+pub(crate) fn meters(x: f64) -> usize {
     (x * 100.0).round() as usize
 }
 
-fn all_crossed_roads(clockwise: &Vec<RoadID>, r1: RoadID, r2: RoadID) -> Vec<RoadID> {
+pub(crate) fn all_crossed_roads(clockwise: &Vec<RoadID>, r1: RoadID, r2: RoadID) -> Vec<RoadID> {
     // TODO Do we care about left vs right turns?
 
     // No possible crossings for small intersections
@@ -318,7 +416,7 @@ fn all_crossed_roads(clockwise: &Vec<RoadID>, r1: RoadID, r2: RoadID) -> Vec<Roa
 }
 
 // TODO Have I written this somewhere else already, or can we track direction as we go?
-fn roads_to_steps(graph: &Graph, roads: Vec<RoadID>) -> Result<Vec<(RoadID, Dir)>> {
+pub(crate) fn roads_to_steps(graph: &Graph, roads: Vec<RoadID>) -> Result<Vec<(RoadID, Dir)>> {
     if roads.is_empty() {
         bail!("roads_to_steps got an empty path");
     }
@@ -0,0 +1,224 @@
+use std::collections::BTreeMap;
+
+use anyhow::Result;
+use graph::RoadID;
+use serde::Serialize;
+
+use crate::existing::classify_existing_osm_infra;
+use crate::reachable::Reachability;
+use crate::routes::gradient_group;
+use crate::{Dir, InfraType, MapModel, Tier, POI_KINDS};
+
+/// Everything about one route, for assessing it in isolation
+#[derive(Serialize)]
+pub struct RouteReport {
+    pub id: usize,
+    pub name: String,
+    pub notes: String,
+    pub tier: Tier,
+    pub infra_type: InfraType,
+    pub length_meters: f64,
+    #[serde(flatten)]
+    pub breakdown: LengthBreakdown,
+
+    /// (Distance along the route, elevation relative to the start), both in meters
+    pub elevation_profile: Vec<(f64, f64)>,
+    pub total_climb_meters: f64,
+
+    /// The sum of precalculated demand times length in meters
+    pub demand_meters: f64,
+    pub max_demand: usize,
+    pub high_demand_length: f64,
+    pub medium_demand_length: f64,
+
+    /// How many more POIs of each kind (and population, under "population") are reachable with
+    /// this route than without it
+    pub newly_reachable: BTreeMap<String, usize>,
+
+    /// Parts of the route where the infrastructure type doesn't fit in the available street space
+    pub street_space_issues: Vec<StreetSpaceIssue>,
+
+    /// In pounds, using placeholder costs per kilometer. Roads that already have this
+    /// infrastructure cost nothing.
+    pub estimated_cost: f64,
+}
+
+/// How the length of some roads splits up, in meters
+#[derive(Default, Serialize)]
+pub struct LengthBreakdown {
+    /// Keyed by the debug name of the LoS
+    pub length_by_los: BTreeMap<String, f64>,
+    /// Keyed by the debug name of the InfraType
+    pub length_by_infra_type: BTreeMap<String, f64>,
+    /// Keyed by `gradient_group`
+    pub length_by_gradient_group: BTreeMap<String, f64>,
+}
+
+#[derive(Serialize)]
+pub struct StreetSpaceIssue {
+    pub road: usize,
+    pub name: Option<String>,
+    pub length_meters: f64,
+}
+
+impl MapModel {
+    pub fn get_route_report(&mut self, id: usize) -> Result<RouteReport> {
+        let without = self.with_route_removed(id, |model| model.count_reachable_pois())??;
+        let with = self.count_reachable_pois()?;
+        let newly_reachable = with
+            .into_iter()
+            .map(|(key, count)| {
+                let before = without.get(&key).cloned().unwrap_or(0);
+                (key, count.saturating_sub(before))
+            })
+            .collect();
+
+        let route = &self.routes[&id];
+        let mut length_meters = 0.0;
+        let breakdown = self.length_breakdown(route.roads.iter().map(|(r, _)| *r));
+        let mut elevation_profile = vec![(0.0, 0.0)];
+        let mut elevation = 0.0;
+        let mut total_climb_meters = 0.0;
+        let mut demand_meters = 0.0;
+        let mut max_demand = 0;
+        let mut high_demand_length = 0.0;
+        let mut medium_demand_length = 0.0;
+        let mut street_space_issues = Vec::new();
+        let mut estimated_cost = 0.0;
+
+        for (r, dir) in &route.roads {
+            let road = &self.graph.roads[r.0];
+            let length = road.length_meters;
+            let infra_type = self.get_infra_type(*r);
+            length_meters += length;
+
+            // The gradient is for the forwards direction
+            let gradient = match dir {
+                Dir::Forwards => self.gradients[r.0],
+                Dir::Backwards => -self.gradients[r.0],
+            };
+            let rise = gradient / 100.0 * length;
+            elevation += rise;
+            total_climb_meters += rise.max(0.0);
+            elevation_profile.push((length_meters, elevation));
+
+            let demand = self.precalculated_demands[r.0];
+            demand_meters += demand as f64 * length;
+            max_demand = max_demand.max(demand);
+            if demand >= self.high_demand_threshold {
+                high_demand_length += length;
+            } else if demand >= self.medium_demand_threshold {
+                medium_demand_length += length;
+            }
+
+            if !self.does_infra_type_fit(*r, infra_type) {
+                street_space_issues.push(StreetSpaceIssue {
+                    road: r.0,
+                    name: road.osm_tags.get("name").cloned(),
+                    length_meters: length,
+                });
+            }
+
+            estimated_cost += infra_type.build_cost(
+                length,
+                classify_existing_osm_infra(self.is_offroad[r.0], &road.osm_tags),
+            );
+        }
+
+        Ok(RouteReport {
+            id,
+            name: route.name.clone(),
+            notes: route.notes.clone(),
+            tier: route.tier,
+            infra_type: route.infra_type,
+            length_meters,
+            breakdown,
+
+            elevation_profile,
+            total_climb_meters,
+
+            demand_meters,
+            max_demand,
+            high_demand_length,
+            medium_demand_length,
+
+            newly_reachable,
+
+            street_space_issues,
+
+            estimated_cost,
+        })
+    }
+
+    /// Sums the length of some roads by LoS, infrastructure type, and gradient
+    pub(crate) fn length_breakdown(&self, roads: impl Iterator<Item = RoadID>) -> LengthBreakdown {
+        let mut breakdown = LengthBreakdown::default();
+        for r in roads {
+            let length = self.graph.roads[r.0].length_meters;
+            *breakdown
+                .length_by_los
+                .entry(format!("{:?}", self.los[r.0]))
+                .or_insert(0.0) += length;
+            *breakdown
+                .length_by_infra_type
+                .entry(format!("{:?}", self.get_infra_type(r)))
+                .or_insert(0.0) += length;
+            *breakdown
+                .length_by_gradient_group
+                .entry(gradient_group(self.gradients[r.0]).to_string())
+                .or_insert(0.0) += length;
+        }
+        breakdown
+    }
+
+    /// Temporarily remove one route, calculate something, then restore everything
+    pub(crate) fn with_route_removed<T, F: FnOnce(&mut MapModel) -> T>(
+        &mut self,
+        id: usize,
+        f: F,
+    ) -> Result<T> {
+        let Some(route) = self.routes.remove(&id) else {
+            bail!("No route {id}");
+        };
+        let quiet_router_ok = self.quiet_router_ok;
+        self.recalculate_after_edits();
+
+        let result = f(self);
+        // If f recalculated the quiet router, its costs are for the network without this route
+        let router_changed = self.quiet_router_ok;
+
+        self.routes.insert(id, route);
+        self.recalculate_after_edits();
+        // Otherwise, the edge costs haven't really changed
+        self.quiet_router_ok = quiet_router_ok && !router_changed;
+        Ok(result)
+    }
+
+    /// How many POIs of each kind, and how much population, is reachable
+    fn count_reachable_pois(&self) -> Result<BTreeMap<String, usize>> {
+        let reachable = self.get_reachable_network();
+        let mut counts = BTreeMap::new();
+        for kind in POI_KINDS {
+            let count = self
+                .get_pois_of_kind(kind)?
+                .iter()
+                .filter(|roads| reachable.covers_any(roads))
+                .count();
+            counts.insert(kind.to_string(), count);
+        }
+        counts.insert(
+            "population".to_string(),
+            reachable_population(self, &reachable),
+        );
+        Ok(counts)
+    }
+}
+
+fn reachable_population(model: &MapModel, reachable: &Reachability) -> usize {
+    model
+        .data_zones
+        .iter()
+        .filter(|x| reachable.covers_any(&x.roads))
+        .map(|x| x.population)
+        .sum()
+}
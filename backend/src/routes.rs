@@ -5,6 +5,7 @@ use geo::{Haversine, Length, LineString};
 use geojson::{Feature, FeatureCollection, Geometry};
 use graph::{Graph, PathStep, Position, RoadID};
 use serde::{Deserialize, Serialize};
+use serde_json::Value;
 use utils::{collapse_degree_2, KeyedLineString};
 
 use crate::route_snapper::roads_to_waypoints;
@@ -217,10 +218,82 @@ impl MapModel {
                 "id_counter": self.id_counter,
                 "version": 2,
                 "study_area_name": self.study_area_name.clone(),
+                "targets": self.targets,
+                "reachability_limit_meters": self.reachability_limit_meters,
+                "crossings": self.save_crossings(),
+                "current_scenario": self.current_scenario,
+                "other_scenarios": self.save_other_scenarios(),
             }))),
         }
     }
 
+    /// Loads a savefile produced by `get_all_routes`
+    pub fn load_savefile(&mut self, input: &str) -> Result<()> {
+        let savefile: FeatureCollection = serde_json::from_str(input)?;
+        let Some(foreign_members) = savefile.foreign_members.as_ref() else {
+            bail!("GeoJSON is missing foreign members section");
+        };
+
+        let mut ok = false;
+        if let Some(Value::Number(num)) = foreign_members.get("version") {
+            if let Some(version) = num.as_u64() {
+                ok = version == 2;
+            }
+        }
+        if !ok {
+            bail!("Savefile is out-of-date");
+        }
+
+        let Some(Value::String(study_area_name)) = foreign_members.get("study_area_name") else {
+            bail!("Savefile is mising study_area_name");
+        };
+        if study_area_name != &self.study_area_name {
+            bail!(
+                "Savefile is for {study_area_name}, but you are currently in {}",
+                self.study_area_name
+            );
+        }
+
+        self.id_counter = match foreign_members.get("id_counter") {
+            Some(Value::Number(num)) => match num.as_u64() {
+                Some(num) => num as usize,
+                None => {
+                    bail!("Savefile has bad id_counter");
+                }
+            },
+            _ => {
+                bail!("Savefile is missing id_counter");
+            }
+        };
+
+        // Older savefiles don't have targets
+        self.targets = match foreign_members.get("targets") {
+            Some(value) => serde_json::from_value(value.clone())?,
+            None => Vec::new(),
+        };
+        self.set_reachability_limit(match foreign_members.get("reachability_limit_meters") {
+            Some(value) => serde_json::from_value(value.clone())?,
+            None => None,
+        })?;
+        match foreign_members.get("crossings") {
+            Some(value) => self.load_crossings(serde_json::from_value(value.clone())?),
+            None => self.load_crossings(Vec::new()),
+        }
+        self.current_scenario = match foreign_members.get("current_scenario") {
+            Some(value) => serde_json::from_value(value.clone())?,
+            None => crate::scenarios::default_scenario_name(),
+        };
+        self.load_other_scenarios(foreign_members.get("other_scenarios"))?;
+
+        for feature in savefile.features {
+            let route: SavedRoute = geojson::de::from_feature(feature)?;
+            self.routes.insert(route.id, route.to_in_memory(self));
+        }
+
+        self.recalculate_after_edits();
+        Ok(())
+    }
+
     pub fn get_route(&self, id: usize) -> Result<Feature> {
         let Some(route) = self.routes.get(&id) else {
             bail!("No route {id}");
@@ -228,7 +301,7 @@ impl MapModel {
         Ok(route.to_gj(id))
     }
 
-    fn tier_for_import(&self, r: RoadID) -> Tier {
+    pub(crate) fn tier_for_import(&self, r: RoadID) -> Tier {
         if !self.within_settlement[r.0] {
             return Tier::LongDistance;
         }
@@ -281,7 +354,7 @@ impl MapModel {
             imports.push((road_id, infra_type, self.tier_for_import(road_id)));
         }
 
-        self.import_roads(imports);
+        self.import_roads(imports, "imported from existing network");
     }
 
     pub fn import_arterial_roads(&mut self) {
@@ -302,7 +375,7 @@ impl MapModel {
             }
         }
 
-        self.import_roads(imports);
+        self.import_roads(imports, "imported from existing network");
     }
 
     /// Split a route into sections, returning a FeatureCollection & SseDetails
@@ -519,7 +592,9 @@ impl MapModel {
         Ok(serde_json::to_vec(&sections)?)
     }
 
-    fn import_roads(&mut self, imports: Vec<(RoadID, InfraType, Tier)>) {
+    /// Create routes from individual roads, grouping them into sections. Every route gets the
+    /// same `notes`, describing where it came from.
+    pub(crate) fn import_roads(&mut self, imports: Vec<(RoadID, InfraType, Tier)>, notes: &str) {
         let used_roads = self.used_roads();
 
         // Create individual segments to import
@@ -572,7 +647,7 @@ impl MapModel {
                 roads,
 
                 name,
-                notes: "imported from existing network".to_string(),
+                notes: notes.to_string(),
                 infra_type,
                 override_infra_type: false,
                 tier,
@@ -593,7 +668,7 @@ impl MapModel {
     }
 }
 
-fn fix_dir(pair: (RoadID, bool)) -> (RoadID, Dir) {
+pub(crate) fn fix_dir(pair: (RoadID, bool)) -> (RoadID, Dir) {
     (
         pair.0,
         if pair.1 {
@@ -648,7 +723,7 @@ pub fn end_pos((road, dir): (RoadID, Dir), graph: &Graph) -> Position {
 }
 
 impl InMemoryRoute {
-    fn to_gj(&self, id: usize) -> Feature {
+    pub(crate) fn to_gj(&self, id: usize) -> Feature {
         geojson::ser::to_feature(SavedRoute {
             geometry: self.linestring_wgs84.clone(),
             id,
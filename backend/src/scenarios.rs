@@ -0,0 +1,182 @@
+use std::collections::{BTreeMap, HashMap};
+
+use anyhow::Result;
+use geojson::FeatureCollection;
+use graph::IntersectionID;
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+
+use crate::crossings::SavedCrossing;
+use crate::routes::{InMemoryRoute, SavedRoute};
+use crate::{CrossingKind, MapModel};
+
+pub fn default_scenario_name() -> String {
+    "Main".to_string()
+}
+
+/// A set of edits that isn't currently being worked on
+#[derive(Clone, Default)]
+pub struct Scenario {
+    routes: HashMap<usize, InMemoryRoute>,
+    id_counter: usize,
+    crossings: HashMap<IntersectionID, CrossingKind>,
+}
+
+/// How other scenarios are stored in the savefile
+#[derive(Serialize, Deserialize)]
+pub(crate) struct SavedScenario {
+    id_counter: usize,
+    crossings: Vec<SavedCrossing>,
+    routes: FeatureCollection,
+}
+
+impl MapModel {
+    /// All scenario names, in order
+    pub fn list_scenarios(&self) -> Vec<String> {
+        let mut names: Vec<String> = self.other_scenarios.keys().cloned().collect();
+        names.push(self.current_scenario.clone());
+        names.sort();
+        names
+    }
+
+    pub fn get_current_scenario(&self) -> &str {
+        &self.current_scenario
+    }
+
+    /// Stash the current scenario and start editing a different one
+    pub fn switch_scenario(&mut self, name: &str) -> Result<()> {
+        if name == self.current_scenario {
+            return Ok(());
+        }
+        let Some(scenario) = self.other_scenarios.remove(name) else {
+            bail!("No scenario {name}");
+        };
+
+        let current = Scenario {
+            routes: std::mem::take(&mut self.routes),
+            id_counter: self.id_counter,
+            crossings: std::mem::take(&mut self.crossings),
+        };
+        self.other_scenarios
+            .insert(std::mem::take(&mut self.current_scenario), current);
+
+        self.routes = scenario.routes;
+        self.id_counter = scenario.id_counter;
+        self.crossings = scenario.crossings;
+        self.current_scenario = name.to_string();
+        self.recalculate_after_edits();
+        Ok(())
+    }
+
+    /// Copy the current scenario to a new one, without switching to it
+    pub fn clone_scenario(&mut self, new_name: &str) -> Result<()> {
+        self.check_new_scenario_name(new_name)?;
+        self.other_scenarios.insert(
+            new_name.to_string(),
+            Scenario {
+                routes: self.routes.clone(),
+                id_counter: self.id_counter,
+                crossings: self.crossings.clone(),
+            },
+        );
+        Ok(())
+    }
+
+    pub fn rename_scenario(&mut self, name: &str, new_name: &str) -> Result<()> {
+        self.check_new_scenario_name(new_name)?;
+        if name == self.current_scenario {
+            self.current_scenario = new_name.to_string();
+        } else if let Some(scenario) = self.other_scenarios.remove(name) {
+            self.other_scenarios.insert(new_name.to_string(), scenario);
+        } else {
+            bail!("No scenario {name}");
+        }
+        Ok(())
+    }
+
+    /// Only scenarios not being edited can be deleted
+    pub fn delete_scenario(&mut self, name: &str) -> Result<()> {
+        if name == self.current_scenario {
+            bail!("Can't delete the current scenario {name}; switch to another first");
+        }
+        if self.other_scenarios.remove(name).is_none() {
+            bail!("No scenario {name}");
+        }
+        Ok(())
+    }
+
+    /// Returns JSON with `Stats` and network lengths per scenario name
+    pub fn compare_scenarios(&mut self) -> Result<String> {
+        let original = self.current_scenario.clone();
+        let mut results = serde_json::Map::new();
+        for name in self.list_scenarios() {
+            self.switch_scenario(&name)?;
+            let lengths: Value = serde_json::from_str(&self.get_network_lengths()?)?;
+            results.insert(
+                name,
+                serde_json::json!({
+                    "stats": self.get_stats(),
+                    "network_lengths": lengths,
+                }),
+            );
+        }
+        self.switch_scenario(&original)?;
+        Ok(serde_json::to_string(&results)?)
+    }
+
+    /// For the savefile. The current scenario is saved separately.
+    pub(crate) fn save_other_scenarios(&self) -> BTreeMap<String, SavedScenario> {
+        let mut saved = BTreeMap::new();
+        for (name, scenario) in &self.other_scenarios {
+            saved.insert(
+                name.clone(),
+                SavedScenario {
+                    id_counter: scenario.id_counter,
+                    crossings: self.crossings_to_saved(&scenario.crossings),
+                    routes: FeatureCollection {
+                        features: scenario.routes.iter().map(|(id, r)| r.to_gj(*id)).collect(),
+                        bbox: None,
+                        foreign_members: None,
+                    },
+                },
+            );
+        }
+        saved
+    }
+
+    pub(crate) fn load_other_scenarios(&mut self, value: Option<&Value>) -> Result<()> {
+        self.other_scenarios.clear();
+        let Some(value) = value else {
+            return Ok(());
+        };
+        let saved: BTreeMap<String, SavedScenario> = serde_json::from_value(value.clone())?;
+        for (name, saved) in saved {
+            // The stored copy would be unreachable
+            if name == self.current_scenario {
+                bail!("Savefile has two scenarios called {name}");
+            }
+            let mut routes = HashMap::new();
+            for feature in saved.routes.features {
+                let route: SavedRoute = geojson::de::from_feature(feature)?;
+                routes.insert(route.id, route.to_in_memory(self));
+            }
+            let scenario = Scenario {
+                routes,
+                id_counter: saved.id_counter,
+                crossings: self.snap_saved_crossings(saved.crossings),
+            };
+            self.other_scenarios.insert(name, scenario);
+        }
+        Ok(())
+    }
+
+    fn check_new_scenario_name(&self, name: &str) -> Result<()> {
+        if name.is_empty() {
+            bail!("Scenario names can't be empty");
+        }
+        if name == self.current_scenario || self.other_scenarios.contains_key(name) {
+            bail!("There's already a scenario called {name}");
+        }
+        Ok(())
+    }
+}
@@ -0,0 +1,152 @@
+use std::collections::BTreeMap;
+
+use anyhow::Result;
+use enum_map::EnumMap;
+use graph::Timer;
+use serde::Serialize;
+
+use crate::targets::metric_values;
+use crate::{LevelOfService, MapModel};
+
+/// The results of every combination of parameters
+#[derive(Serialize)]
+pub struct SensitivityReport {
+    pub runs: Vec<SensitivityRun>,
+    /// Per KPI, how much it moves across all runs
+    pub summary: BTreeMap<String, KpiSpread>,
+}
+
+#[derive(Serialize)]
+pub struct SensitivityRun {
+    /// How much the default penalties above 1 are stretched. 1 uses the defaults.
+    pub penalty_scale: f64,
+    /// How much the default demand thresholds are multiplied. 1 uses the defaults.
+    pub threshold_scale: f64,
+    pub penalties: BTreeMap<String, f64>,
+    pub high_demand_threshold: usize,
+    pub medium_demand_threshold: usize,
+    pub kpis: BTreeMap<String, f64>,
+}
+
+#[derive(Serialize)]
+pub struct KpiSpread {
+    /// The value with default parameters, if they were part of the grid
+    pub reference: Option<f64>,
+    pub min: f64,
+    pub max: f64,
+    /// max - min
+    pub spread: f64,
+}
+
+impl MapModel {
+    /// Recalculate stats, slow stats, and OD LoS shares for every combination of penalty and
+    /// threshold scales. A penalty scale of `k` turns each default penalty `p` into `1 + (p - 1)
+    /// * k`, so high LoS stays at 1. Everything is restored afterwards. This is very slow.
+    pub fn run_sensitivity(
+        &mut self,
+        penalty_scales: &[f64],
+        threshold_scales: &[f64],
+        timer: &mut Timer,
+    ) -> Result<SensitivityReport> {
+        let orig_thresholds = (self.high_demand_threshold, self.medium_demand_threshold);
+        let orig_penalties = std::mem::take(&mut self.penalty_overrides);
+
+        let mut runs = Vec::new();
+        let mut result = Ok(());
+        'grid: for penalty_scale in penalty_scales {
+            for threshold_scale in threshold_scales {
+                match self.sensitivity_run(*penalty_scale, *threshold_scale, orig_thresholds, timer)
+                {
+                    Ok(run) => runs.push(run),
+                    Err(err) => {
+                        result = Err(err);
+                        break 'grid;
+                    }
+                }
+            }
+        }
+
+        (self.high_demand_threshold, self.medium_demand_threshold) = orig_thresholds;
+        self.set_penalty_overrides(orig_penalties);
+        result?;
+
+        let mut summary = BTreeMap::new();
+        for run in &runs {
+            let is_reference = run.penalty_scale == 1.0 && run.threshold_scale == 1.0;
+            for (key, value) in &run.kpis {
+                let spread = summary.entry(key.clone()).or_insert(KpiSpread {
+                    reference: None,
+                    min: *value,
+                    max: *value,
+                    spread: 0.0,
+                });
+                spread.min = spread.min.min(*value);
+                spread.max = spread.max.max(*value);
+                spread.spread = spread.max - spread.min;
+                if is_reference {
+                    spread.reference = Some(*value);
+                }
+            }
+        }
+
+        Ok(SensitivityReport { runs, summary })
+    }
+
+    fn sensitivity_run(
+        &mut self,
+        penalty_scale: f64,
+        threshold_scale: f64,
+        (high_demand_threshold, medium_demand_threshold): (usize, usize),
+        timer: &mut Timer,
+    ) -> Result<SensitivityRun> {
+        timer.step(format!(
+            "sensitivity with penalty scale {penalty_scale}, threshold scale {threshold_scale}"
+        ));
+
+        let penalties: EnumMap<LevelOfService, f64> =
+            EnumMap::from_fn(|los: LevelOfService| 1.0 + (los.penalty() - 1.0) * penalty_scale);
+        let penalties_by_name = penalties
+            .iter()
+            .map(|(los, x)| (format!("{los:?}"), *x))
+            .collect();
+        self.set_penalty_overrides(Some(penalties));
+        self.high_demand_threshold = scale(high_demand_threshold, threshold_scale);
+        self.medium_demand_threshold = scale(medium_demand_threshold, threshold_scale);
+
+        self.recalculate_quiet_router(timer);
+        let stats = self.get_stats();
+        let slow_stats = self.get_slow_stats(timer);
+
+        let mut kpis = BTreeMap::new();
+        for (key, value) in metric_values(&stats, Some(&slow_stats))? {
+            if let Some(x) = value.as_f64() {
+                kpis.insert(key, x);
+            }
+        }
+
+        let fast_sample = true;
+        let od = self.od_counts(fast_sample, "bicycle_quiet")?;
+        let mut out = serde_json::Map::new();
+        od.describe(self, &mut out)?;
+        if let Some(serde_json::Value::Object(percents)) = out.get("od_percents_los") {
+            for (los, value) in percents {
+                if let Some(x) = value.as_f64() {
+                    kpis.insert(format!("od_percent_los_{los}"), x);
+                }
+            }
+        }
+
+        Ok(SensitivityRun {
+            penalty_scale,
+            threshold_scale,
+            penalties: penalties_by_name,
+            high_demand_threshold: self.high_demand_threshold,
+            medium_demand_threshold: self.medium_demand_threshold,
+            kpis,
+        })
+    }
+}
+
+fn scale(x: usize, factor: f64) -> usize {
+    (x as f64 * factor).round() as usize
+}
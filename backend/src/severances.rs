@@ -0,0 +1,250 @@
+use std::collections::{BinaryHeap, HashMap, HashSet};
+
+use anyhow::Result;
+use geojson::FeatureCollection;
+use graph::{IntersectionID, RoadID};
+use serde::Serialize;
+use utils::PriorityQueueItem;
+
+use crate::reachable::{meters, Reachability};
+use crate::{utils::into_object_value, LevelOfService, MapModel};
+
+/// A hypothetical change that might make more things reachable
+#[derive(Clone, Copy, PartialEq)]
+enum Upgrade {
+    /// Make this road high LoS
+    Road(RoadID),
+    /// Allow all movements through this junction, as if there was a safe crossing
+    Junction(IntersectionID),
+}
+
+/// What becomes reachable from one upgrade
+#[derive(Default, Serialize)]
+struct Gain {
+    population: usize,
+    schools: usize,
+    gp_hospitals: usize,
+    railway_stations: usize,
+    greenspaces: usize,
+    town_centres: usize,
+    settlements: usize,
+}
+
+impl Gain {
+    fn total_pois(&self) -> usize {
+        self.schools
+            + self.gp_hospitals
+            + self.railway_stations
+            + self.greenspaces
+            + self.town_centres
+            + self.settlements
+    }
+}
+
+impl MapModel {
+    /// For each severance road and blocked junction next to the reachable network, calculate how
+    /// much population and how many POIs would become reachable if only that one thing was fixed.
+    /// Returns a GeoJSON FeatureCollection ranked by population, then POIs, with at most `limit`
+    /// results.
+    pub fn rank_severances(&self, limit: usize) -> Result<Vec<u8>> {
+        let base = self.get_reachable_network();
+
+        // Only severances touching something reachable could possibly matter
+        let mut touches_reachable: HashSet<IntersectionID> = HashSet::new();
+        for r in base.network.iter().chain(base.reachable.iter()) {
+            let road = &self.graph.roads[r.0];
+            touches_reachable.insert(road.src_i);
+            touches_reachable.insert(road.dst_i);
+        }
+
+        let mut candidates = Vec::new();
+        for r in &base.severances {
+            let road = &self.graph.roads[r.0];
+            if touches_reachable.contains(&road.src_i) || touches_reachable.contains(&road.dst_i) {
+                candidates.push(Upgrade::Road(*r));
+            }
+        }
+        for i in &touches_reachable {
+            // Smaller junctions never have crossings
+            let roads = &self.graph.intersections[i.0].roads;
+            if roads.len() >= 4
+                && !self.has_crossing(*i)
+                && roads.iter().any(|r| self.los[r.0] != LevelOfService::High)
+            {
+                candidates.push(Upgrade::Junction(*i));
+            }
+        }
+        info!("Ranking {} possible severance fixes", candidates.len());
+
+        let mut results = Vec::new();
+        for upgrade in candidates {
+            let gain = self.gain_from_upgrade(&base, &self.newly_reachable(&base, upgrade));
+            if gain.population > 0 || gain.total_pois() > 0 {
+                results.push((upgrade, gain));
+            }
+        }
+        results.sort_by_key(|(_, gain)| (gain.population, gain.total_pois()));
+        results.reverse();
+        results.truncate(limit);
+
+        let mut features = Vec::new();
+        for (rank, (upgrade, gain)) in results.into_iter().enumerate() {
+            let mut f = match upgrade {
+                Upgrade::Road(r) => {
+                    let road = &self.graph.roads[r.0];
+                    let mut f = self.graph.mercator.to_wgs84_gj(&road.linestring);
+                    f.set_property("kind", "road");
+                    f.set_property("id", r.0);
+                    f.set_property("name", road.osm_tags.get("name").cloned());
+                    f.set_property("los", serde_json::to_value(self.los[r.0])?);
+                    f
+                }
+                Upgrade::Junction(i) => {
+                    let mut f = self
+                        .graph
+                        .mercator
+                        .to_wgs84_gj(&self.graph.intersections[i.0].point);
+                    f.set_property("kind", "junction");
+                    f.set_property("id", i.0);
+                    f
+                }
+            };
+            f.set_property("rank", rank + 1);
+            f.set_property("total_pois", gain.total_pois());
+            for (key, value) in into_object_value(serde_json::to_value(&gain)?) {
+                f.set_property(key, value);
+            }
+            features.push(f);
+        }
+
+        Ok(serde_json::to_vec(&FeatureCollection {
+            features,
+            bbox: None,
+            foreign_members: None,
+        })?)
+    }
+
+    /// Find roads that aren't reachable now, but would be after this upgrade. Only improvements
+    /// spreading out from the upgrade are explored, so this is much faster than recalculating
+    /// everything.
+    fn newly_reachable(&self, base: &Reachability, upgrade: Upgrade) -> HashSet<RoadID> {
+        let limit = self.reachability_limit_meters.map(meters);
+        let is_high_los =
+            |r: RoadID| self.los[r.0] == LevelOfService::High || upgrade == Upgrade::Road(r);
+        let has_crossing =
+            |i: IntersectionID| upgrade == Upgrade::Junction(i) || self.has_crossing(i);
+        let base_cost = |r: RoadID| base.distance(r).map(meters);
+
+        // A drawn road that isn't high LoS would become part of the network
+        let upgraded_network = match upgrade {
+            Upgrade::Road(r) if self.infra_types[r.0].is_some() => Some(r),
+            _ => None,
+        };
+        let is_network = |r: RoadID| base.network.contains(&r) || upgraded_network == Some(r);
+
+        // Anything newly reachable has to be reached through the upgraded place, so start from
+        // everything already reachable there
+        let mut queue: BinaryHeap<PriorityQueueItem<usize, RoadID>> = BinaryHeap::new();
+        let intersections = match upgrade {
+            Upgrade::Road(r) => {
+                let road = &self.graph.roads[r.0];
+                vec![road.src_i, road.dst_i]
+            }
+            Upgrade::Junction(i) => vec![i],
+        };
+        for i in intersections {
+            for r in &self.graph.intersections[i.0].roads {
+                if let Some(cost) = base_cost(*r) {
+                    queue.push(PriorityQueueItem::new(cost, *r));
+                }
+            }
+        }
+        if let Some(r) = upgraded_network {
+            queue.push(PriorityQueueItem::new(0, r));
+        }
+
+        let mut best: HashMap<RoadID, usize> = HashMap::new();
+        let mut newly_reachable = HashSet::new();
+        while let Some(item) = queue.pop() {
+            let r = item.value;
+            if best.get(&r).is_some_and(|cost| *cost <= item.cost) {
+                continue;
+            }
+            best.insert(r, item.cost);
+            if base_cost(r).is_none() {
+                newly_reachable.insert(r);
+            }
+
+            let cost_after = if is_network(r) {
+                item.cost
+            } else {
+                item.cost + meters(self.graph.roads[r.0].length_meters)
+            };
+            if limit.is_some_and(|limit| cost_after > limit) {
+                continue;
+            }
+
+            for r2 in self.next_reachable_roads_with(r, is_high_los, has_crossing) {
+                if !is_high_los(r2) {
+                    continue;
+                }
+                // Only keep going if this is an improvement over how things are reached today
+                if base_cost(r2).is_some_and(|cost| cost <= cost_after)
+                    || best.get(&r2).is_some_and(|cost| *cost <= cost_after)
+                {
+                    continue;
+                }
+                queue.push(PriorityQueueItem::new(cost_after, r2));
+            }
+        }
+        newly_reachable
+    }
+
+    fn gain_from_upgrade(&self, base: &Reachability, newly_reachable: &HashSet<RoadID>) -> Gain {
+        let mut gain = Gain::default();
+        if newly_reachable.is_empty() {
+            return gain;
+        }
+        let newly_covers_any = |roads: &HashSet<RoadID>| {
+            !base.covers_any(roads) && !newly_reachable.is_disjoint(roads)
+        };
+
+        gain.schools = self
+            .schools
+            .iter()
+            .filter(|x| newly_reachable.contains(&x.road))
+            .count();
+        gain.gp_hospitals = self
+            .gp_hospitals
+            .iter()
+            .filter(|x| newly_reachable.contains(&x.road))
+            .count();
+        gain.railway_stations = self
+            .railway_stations
+            .iter()
+            .filter(|x| newly_reachable.contains(&x.road))
+            .count();
+        gain.greenspaces = self
+            .greenspaces
+            .iter()
+            .filter(|x| newly_covers_any(&x.roads))
+            .count();
+        gain.town_centres = self
+            .town_centres
+            .iter()
+            .filter(|x| newly_covers_any(&x.roads))
+            .count();
+        gain.settlements = self
+            .settlements
+            .iter()
+            .filter(|x| newly_covers_any(&x.roads))
+            .count();
+        gain.population = self
+            .data_zones
+            .iter()
+            .filter(|x| newly_covers_any(&x.roads))
+            .map(|x| x.population)
+            .sum();
+        gain
+    }
+}
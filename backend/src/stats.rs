@@ -38,6 +38,12 @@ pub struct Stats {
 
     num_connected_components: usize,
     num_settlements: usize,
+
+    num_crossings: usize,
+    total_crossing_cost: f64,
+
+    high_los_junction_movements: usize,
+    total_junction_movements: usize,
 }
 
 impl MapModel {
@@ -189,6 +195,9 @@ impl MapModel {
             }
         }
 
+        let (high_los_junction_movements, total_junction_movements) =
+            self.count_junction_movements();
+
         let density_network_in_settlements = if length_in_settlements > 0.0 {
             Some(self.total_settlement_area_m2 / length_in_settlements)
         } else {
@@ -226,6 +235,12 @@ impl MapModel {
 
             num_connected_components: self.num_connected_components(),
             num_settlements: self.settlements.len(),
+
+            num_crossings: self.crossings.len(),
+            total_crossing_cost: self.crossings.values().map(|kind| kind.cost()).sum(),
+
+            high_los_junction_movements,
+            total_junction_movements,
         }
     }
 
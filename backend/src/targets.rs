@@ -0,0 +1,230 @@
+use anyhow::Result;
+use serde::{Deserialize, Serialize};
+
+use crate::{od::SlowStats, stats::Stats, MapModel};
+
+/// A KPI target adopted by a council, like "90% of schools reachable". Targets are stored in the
+/// savefile.
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct Target {
+    pub name: String,
+    /// The name of a field in `Stats` or `SlowStats`
+    pub metric: String,
+    /// If specified, `metric` is divided by this other field. This expresses things like "60% of
+    /// arterial roads covered", using `covered_arterial_road_length` relative to
+    /// `total_arterial_road_length`.
+    #[serde(default)]
+    pub relative_to: Option<String>,
+    pub comparison: Comparison,
+    pub value: f64,
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Serialize, Deserialize)]
+pub enum Comparison {
+    AtLeast,
+    AtMost,
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Serialize)]
+pub enum Trend {
+    Improved,
+    Worsened,
+    Unchanged,
+    /// The metric isn't available now or for the baseline
+    Unknown,
+}
+
+#[derive(Debug, Serialize)]
+pub struct TargetResult {
+    pub name: String,
+    pub comparison: Comparison,
+    pub target: f64,
+    /// None if the metric couldn't be calculated, like a slow stat that wasn't requested, or a
+    /// ratio with a zero denominator
+    pub current: Option<f64>,
+    pub baseline: Option<f64>,
+    pub pass: bool,
+    /// How far the current value is from meeting the target. 0 when passing.
+    pub gap: Option<f64>,
+    /// The current value relative to `baseline_stats`
+    pub trend: Trend,
+}
+
+/// All metrics that targets can reference, keyed by field name
+pub type MetricValues = serde_json::Map<String, serde_json::Value>;
+
+impl MapModel {
+    pub fn get_targets(&self) -> &Vec<Target> {
+        &self.targets
+    }
+
+    /// Replaces all targets, after checking they reference known metrics
+    pub fn set_targets(&mut self, targets: Vec<Target>) -> Result<()> {
+        let known = metric_values(&self.baseline_stats, Some(&self.baseline_slow_stats))?;
+        for target in &targets {
+            for metric in std::iter::once(&target.metric).chain(target.relative_to.iter()) {
+                if !known.contains_key(metric) {
+                    bail!("Target {} uses unknown metric {metric}", target.name);
+                }
+            }
+        }
+        self.targets = targets;
+        Ok(())
+    }
+
+    /// Do any targets need `SlowStats` to be evaluated?
+    pub fn targets_need_slow_stats(&self) -> bool {
+        let slow_metrics = slow_metric_values(&SlowStats::default());
+        self.targets.iter().any(|t| {
+            slow_metrics.contains_key(&t.metric)
+                || t.relative_to
+                    .as_ref()
+                    .is_some_and(|m| slow_metrics.contains_key(m))
+        })
+    }
+
+    /// Evaluates every target against the current network. If `slow_stats` aren't provided, then
+    /// targets using them won't pass.
+    pub fn evaluate_targets(&self, slow_stats: Option<&SlowStats>) -> Result<Vec<TargetResult>> {
+        let current = metric_values(&self.get_stats(), slow_stats)?;
+        let baseline = metric_values(&self.baseline_stats, Some(&self.baseline_slow_stats))?;
+        Ok(self
+            .targets
+            .iter()
+            .map(|target| {
+                evaluate_target(
+                    target,
+                    target_metric(target, &current),
+                    target_metric(target, &baseline),
+                )
+            })
+            .collect())
+    }
+}
+
+pub fn metric_values(stats: &Stats, slow_stats: Option<&SlowStats>) -> Result<MetricValues> {
+    let serde_json::Value::Object(mut values) = serde_json::to_value(stats)? else {
+        bail!("Stats didn't serialize to an object");
+    };
+    if let Some(slow_stats) = slow_stats {
+        values.extend(slow_metric_values(slow_stats));
+    }
+    Ok(values)
+}
+
+// Only the scalar fields can be used for targets
+fn slow_metric_values(slow_stats: &SlowStats) -> MetricValues {
+    let mut values = serde_json::Map::new();
+    values.insert(
+        "average_weighted_directness".to_string(),
+        slow_stats.average_weighted_directness.into(),
+    );
+    values
+}
+
+fn target_metric(target: &Target, values: &MetricValues) -> Option<f64> {
+    let x = values.get(&target.metric)?.as_f64()?;
+    match target.relative_to {
+        Some(ref denominator) => {
+            let total = values.get(denominator)?.as_f64()?;
+            if total == 0.0 {
+                None
+            } else {
+                Some(x / total)
+            }
+        }
+        None => Some(x),
+    }
+}
+
+fn evaluate_target(target: &Target, current: Option<f64>, baseline: Option<f64>) -> TargetResult {
+    // Positive when the value is on the wrong side of the target
+    let shortfall = |x: f64| match target.comparison {
+        Comparison::AtLeast => target.value - x,
+        Comparison::AtMost => x - target.value,
+    };
+
+    let pass = current.is_some_and(|x| shortfall(x) <= 0.0);
+    let gap = current.map(|x| shortfall(x).max(0.0));
+    let trend = match (current, baseline) {
+        (Some(now), Some(before)) => {
+            // Compare in the direction of the target
+            let change = shortfall(before) - shortfall(now);
+            if change > 0.0 {
+                Trend::Improved
+            } else if change < 0.0 {
+                Trend::Worsened
+            } else {
+                Trend::Unchanged
+            }
+        }
+        _ => Trend::Unknown,
+    };
+
+    TargetResult {
+        name: target.name.clone(),
+        comparison: target.comparison,
+        target: target.value,
+        current,
+        baseline,
+        pass,
+        gap,
+        trend,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn target(comparison: Comparison, value: f64) -> Target {
+        Target {
+            name: "test".to_string(),
+            metric: "x".to_string(),
+            relative_to: None,
+            comparison,
+            value,
+        }
+    }
+
+    #[test]
+    fn test_evaluate_target() {
+        let at_least = target(Comparison::AtLeast, 0.9);
+        let result = evaluate_target(&at_least, Some(0.6), Some(0.5));
+        assert!(!result.pass);
+        assert!((result.gap.unwrap() - 0.3).abs() < 1e-9);
+        assert_eq!(result.trend, Trend::Improved);
+
+        let result = evaluate_target(&at_least, Some(0.95), Some(0.95));
+        assert!(result.pass);
+        assert_eq!(result.gap, Some(0.0));
+        assert_eq!(result.trend, Trend::Unchanged);
+
+        // Lower is better for something like directness
+        let at_most = target(Comparison::AtMost, 1.2);
+        let result = evaluate_target(&at_most, Some(1.5), Some(1.3));
+        assert!(!result.pass);
+        assert_eq!(result.trend, Trend::Worsened);
+
+        let result = evaluate_target(&at_most, None, Some(1.3));
+        assert!(!result.pass);
+        assert_eq!(result.gap, None);
+        assert_eq!(result.trend, Trend::Unknown);
+    }
+
+    #[test]
+    fn test_relative_metric() {
+        let mut values = serde_json::Map::new();
+        values.insert("covered".to_string(), serde_json::json!(30.0));
+        values.insert("total".to_string(), serde_json::json!(50.0));
+        values.insert("empty".to_string(), serde_json::json!(0.0));
+
+        let mut t = target(Comparison::AtLeast, 0.6);
+        t.metric = "covered".to_string();
+        t.relative_to = Some("total".to_string());
+        assert_eq!(target_metric(&t, &values), Some(0.6));
+
+        t.relative_to = Some("empty".to_string());
+        assert_eq!(target_metric(&t, &values), None);
+    }
+}
@@ -0,0 +1,145 @@
+use std::collections::HashSet;
+
+use geojson::FeatureCollection;
+use graph::RoadID;
+
+use crate::{MapModel, Tier};
+
+impl MapModel {
+    /// For every route, suggest a tier from its demand, whether it reaches a town centre, and
+    /// whether it links settlements. Returns a FeatureCollection of the routes, with
+    /// `suggested_tier`, `reasons`, and `disagrees` properties.
+    pub fn suggest_tiers(&self) -> FeatureCollection {
+        let mut features = Vec::new();
+        for (id, route) in &self.routes {
+            let mut total_length = 0.0;
+            let mut outside_length = 0.0;
+            let mut weighted_demand = 0.0;
+            for (r, _) in &route.roads {
+                let length = self.graph.roads[r.0].length_meters;
+                total_length += length;
+                weighted_demand += length * self.precalculated_demands[r.0] as f64;
+                if !self.within_settlement[r.0] {
+                    outside_length += length;
+                }
+            }
+            let roads: HashSet<RoadID> = route.roads.iter().map(|(r, _)| *r).collect();
+
+            let input = TierInput {
+                fraction_outside_settlements: outside_length / total_length,
+                average_demand: (weighted_demand / total_length).round() as usize,
+                high_demand_threshold: self.high_demand_threshold,
+                medium_demand_threshold: self.medium_demand_threshold,
+                reaches_town_centre: self
+                    .town_centres
+                    .iter()
+                    .any(|x| !x.roads.is_disjoint(&roads)),
+                num_settlements_linked: self
+                    .settlements
+                    .iter()
+                    .filter(|x| !x.roads.is_disjoint(&roads))
+                    .count(),
+            };
+            let (suggested_tier, reasons) = suggest_tier(&input);
+
+            let mut f = route.to_gj(*id);
+            f.set_property(
+                "suggested_tier",
+                serde_json::to_value(suggested_tier).unwrap(),
+            );
+            f.set_property("reasons", reasons);
+            f.set_property("disagrees", suggested_tier != route.tier);
+            features.push(f);
+        }
+
+        FeatureCollection {
+            features,
+            bbox: None,
+            foreign_members: None,
+        }
+    }
+}
+
+struct TierInput {
+    fraction_outside_settlements: f64,
+    /// Weighted by length
+    average_demand: usize,
+    high_demand_threshold: usize,
+    medium_demand_threshold: usize,
+    reaches_town_centre: bool,
+    num_settlements_linked: usize,
+}
+
+fn suggest_tier(input: &TierInput) -> (Tier, Vec<String>) {
+    let mut reasons = Vec::new();
+
+    // Long distance routes have to actually connect places, not just be rural
+    if input.fraction_outside_settlements > 0.5 && input.num_settlements_linked >= 2 {
+        reasons.push("mostly outside settlements".to_string());
+        reasons.push(format!(
+            "links {} settlements",
+            input.num_settlements_linked
+        ));
+        return (Tier::LongDistance, reasons);
+    }
+
+    let mut tier = if input.average_demand >= input.high_demand_threshold {
+        reasons.push("high demand".to_string());
+        Tier::Primary
+    } else if input.average_demand >= input.medium_demand_threshold {
+        reasons.push("medium demand".to_string());
+        Tier::Secondary
+    } else {
+        reasons.push("low demand".to_string());
+        Tier::LocalAccess
+    };
+
+    // Routes to town centres are at least secondary
+    if input.reaches_town_centre {
+        reasons.push("reaches a town centre".to_string());
+        if tier == Tier::LocalAccess {
+            tier = Tier::Secondary;
+        }
+    }
+
+    (tier, reasons)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_suggest_tier() {
+        let input = |fraction_outside_settlements, average_demand, reaches_town_centre| TierInput {
+            fraction_outside_settlements,
+            average_demand,
+            high_demand_threshold: 1000,
+            medium_demand_threshold: 100,
+            reaches_town_centre,
+            num_settlements_linked: 2,
+        };
+
+        for (fraction_outside, demand, town_centre, expected) in [
+            (0.9, 5000, false, Tier::LongDistance),
+            (0.0, 5000, false, Tier::Primary),
+            (0.2, 500, false, Tier::Secondary),
+            (0.0, 10, false, Tier::LocalAccess),
+            (0.0, 10, true, Tier::Secondary),
+            (0.0, 5000, true, Tier::Primary),
+        ] {
+            let (actual, _) = suggest_tier(&input(fraction_outside, demand, town_centre));
+            assert_eq!(
+                actual, expected,
+                "for {fraction_outside}, {demand}, {town_centre}"
+            );
+        }
+
+        // A rural route that doesn't link settlements is judged by demand
+        let (actual, _) = suggest_tier(&TierInput {
+            num_settlements_linked: 0,
+            ..input(0.9, 10, false)
+        });
+        assert_eq!(actual, Tier::LocalAccess);
+    }
+}
@@ -0,0 +1,120 @@
+use graph::{PathStep, RoadID, Route};
+use serde::{Deserialize, Serialize};
+
+use crate::{InfraType, MapModel};
+
+/// Different kinds of cyclists ride at different speeds, especially uphill
+#[derive(Clone, Copy, Debug, Default, PartialEq, Serialize, Deserialize)]
+pub enum RiderType {
+    #[default]
+    Standard,
+    EBike,
+    /// Trikes, handcycles, and other adapted cycles
+    Adapted,
+}
+
+impl RiderType {
+    /// On flat ground with good infrastructure. These values are made up, but roughly typical.
+    fn flat_speed_kph(self) -> f64 {
+        match self {
+            RiderType::Standard => 16.0,
+            RiderType::EBike => 22.0,
+            RiderType::Adapted => 12.0,
+        }
+    }
+
+    /// How much each percent of uphill gradient slows the rider down. Electric assistance makes
+    /// hills much easier; heavier adapted cycles struggle more.
+    fn climbing_penalty(self) -> f64 {
+        match self {
+            RiderType::Standard => 0.15,
+            RiderType::EBike => 0.04,
+            RiderType::Adapted => 0.25,
+        }
+    }
+}
+
+/// Riders slow down to share space or on rougher surfaces
+fn infra_type_factor(infra_type: InfraType) -> f64 {
+    match infra_type {
+        InfraType::SharedFootway => 0.7,
+        InfraType::OffRoad => 0.9,
+        InfraType::Segregated
+        | InfraType::SegregatedWithSpeedVolume
+        | InfraType::CycleLane
+        | InfraType::MixedTraffic
+        | InfraType::MixedTrafficWithSpeedVolume => 1.0,
+    }
+}
+
+/// The speed in km/h. The gradient is a percent in the direction of travel, positive uphill.
+pub fn speed_kph(rider: RiderType, infra_type: InfraType, gradient: f64) -> f64 {
+    let flat = rider.flat_speed_kph() * infra_type_factor(infra_type);
+    let speed = if gradient > 0.0 {
+        flat / (1.0 + rider.climbing_penalty() * gradient)
+    } else {
+        // Downhill is faster, but riders brake eventually
+        flat * (1.0 + 0.03 * -gradient).min(1.3)
+    };
+    // Walking pace, pushing the bike up a steep hill
+    speed.max(4.0)
+}
+
+impl MapModel {
+    /// The total time to ride along a route, in minutes
+    pub fn route_travel_time_minutes(&self, route: &Route, rider: RiderType) -> f64 {
+        let mut seconds = 0.0;
+        for step in &route.steps {
+            if let PathStep::Road { road, forwards } = step {
+                seconds += self.road_travel_time_seconds(*road, *forwards, rider);
+            }
+        }
+        seconds / 60.0
+    }
+
+    /// The time to ride along one road in one direction, in seconds
+    pub(crate) fn road_travel_time_seconds(
+        &self,
+        r: RoadID,
+        forwards: bool,
+        rider: RiderType,
+    ) -> f64 {
+        // The gradient is for the forwards direction
+        let gradient = if forwards {
+            self.gradients[r.0]
+        } else {
+            -self.gradients[r.0]
+        };
+        let speed = speed_kph(rider, self.get_infra_type(r), gradient);
+        self.graph.roads[r.0].length_meters / (speed / 3.6)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_speed_kph() {
+        let flat = speed_kph(RiderType::Standard, InfraType::Segregated, 0.0);
+        assert_eq!(flat, 16.0);
+        assert!(speed_kph(RiderType::Standard, InfraType::SharedFootway, 0.0) < flat);
+
+        // Hills slow everyone down, but e-bikes the least
+        let uphill = speed_kph(RiderType::Standard, InfraType::Segregated, 5.0);
+        assert!(uphill < flat);
+        let ebike_uphill = speed_kph(RiderType::EBike, InfraType::Segregated, 5.0);
+        assert!(ebike_uphill / 22.0 > uphill / flat);
+        assert!(speed_kph(RiderType::Adapted, InfraType::Segregated, 5.0) / 12.0 < uphill / flat);
+
+        assert!(speed_kph(RiderType::Standard, InfraType::Segregated, -5.0) > flat);
+        assert_eq!(
+            speed_kph(RiderType::Standard, InfraType::Segregated, -50.0),
+            flat * 1.3
+        );
+        assert_eq!(
+            speed_kph(RiderType::Adapted, InfraType::Segregated, 30.0),
+            4.0
+        );
+    }
+}
@@ -0,0 +1,77 @@
+use anyhow::Result;
+use geojson::FeatureCollection;
+use graph::RoadID;
+use utils::{collapse_degree_2, KeyedLineString};
+
+use crate::{LevelOfService, MapModel};
+
+impl MapModel {
+    /// Find contiguous corridors of high or medium demand roads that aren't part of the network,
+    /// or are but don't have high LoS. Returns a FeatureCollection with at most `limit`
+    /// corridors, ranked by the sum of demand times length.
+    pub fn get_unmet_demand(&self, limit: usize) -> Result<String> {
+        let mut pieces = Vec::new();
+        for (idx, road) in self.graph.roads.iter().enumerate() {
+            if self.precalculated_demands[idx] < self.medium_demand_threshold {
+                continue;
+            }
+            if self.infra_types[idx].is_some() && self.los[idx] == LevelOfService::High {
+                continue;
+            }
+            pieces.push(KeyedLineString {
+                linestring: road.linestring.clone(),
+                ids: vec![(RoadID(idx), true)],
+                // Keep missing roads separate from roads that need improving
+                key: self.infra_types[idx].is_some(),
+            });
+        }
+
+        let mut corridors = Vec::new();
+        for line in collapse_degree_2(pieces) {
+            let mut length = 0.0;
+            let mut demand_meters = 0.0;
+            let mut max_demand = 0;
+            for (r, _) in &line.ids {
+                let road_length = self.graph.roads[r.0].length_meters;
+                length += road_length;
+                demand_meters += road_length * self.precalculated_demands[r.0] as f64;
+                max_demand = max_demand.max(self.precalculated_demands[r.0]);
+            }
+            corridors.push((demand_meters, length, max_demand, line));
+        }
+        corridors.sort_by(|a, b| b.0.total_cmp(&a.0));
+        corridors.truncate(limit);
+
+        let mut features = Vec::new();
+        for (rank, (demand_meters, length, max_demand, line)) in corridors.into_iter().enumerate() {
+            let mut f = self.graph.mercator.to_wgs84_gj(&line.linestring);
+            f.set_property("rank", rank + 1);
+            f.set_property(
+                "name",
+                line.ids
+                    .iter()
+                    .filter_map(|(r, _)| self.graph.roads[r.0].osm_tags.get("name").cloned())
+                    .next(),
+            );
+            f.set_property("on_network", line.key);
+            f.set_property("length_meters", length);
+            f.set_property("demand_meters", demand_meters);
+            f.set_property("max_demand", max_demand);
+            f.set_property(
+                "demand_group",
+                if max_demand >= self.high_demand_threshold {
+                    "high"
+                } else {
+                    "medium"
+                },
+            );
+            features.push(f);
+        }
+
+        Ok(serde_json::to_string(&FeatureCollection {
+            features,
+            bbox: None,
+            foreign_members: None,
+        })?)
+    }
+}
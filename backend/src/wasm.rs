@@ -5,10 +5,12 @@ use geo::{BoundingRect, Centroid, Coord, LineString, MultiPolygon, Polygon, Rect
 use geojson::{Feature, FeatureCollection, Geometry};
 use graph::{RoadID, Timer};
 use serde::Deserialize;
-use serde_json::Value;
 use wasm_bindgen::prelude::*;
 
-use crate::{evaluate::Breakdown, InfraType, MapModel, SavedRoute, SetRouteInput, Tier, Waypoint};
+use crate::{
+    evaluate::Breakdown, journeys::parse_journeys_csv, optimiser::Objective, targets::Target,
+    travel_time::RiderType, CrossingKind, InfraType, MapModel, SetRouteInput, Tier, Waypoint,
+};
 
 static START: Once = Once::new();
 
@@ -153,6 +155,12 @@ impl MapModel {
         Ok(())
     }
 
+    /// Returns GJ of every route with a suggested tier, flagging routes that disagree
+    #[wasm_bindgen(js_name = suggestTiers)]
+    pub fn suggest_tiers_wasm(&self) -> Result<Vec<u8>, JsValue> {
+        serde_json::to_vec(&self.suggest_tiers()).map_err(err_to_js)
+    }
+
     #[wasm_bindgen(js_name = changeInfraType)]
     pub fn change_infra_type_wasm(
         &mut self,
@@ -165,6 +173,63 @@ impl MapModel {
         Ok(())
     }
 
+    /// Snaps to the nearest junction with at least 4 arms, if it's close enough, and adds a
+    /// crossing there. An empty `kind` removes any crossing.
+    #[wasm_bindgen(js_name = setCrossing)]
+    pub fn set_crossing_wasm(&mut self, lon: f64, lat: f64, kind: String) -> Result<(), JsValue> {
+        let kind: Option<CrossingKind> = if kind.is_empty() {
+            None
+        } else {
+            Some(serde_json::from_str(&kind).map_err(err_to_js)?)
+        };
+        let pt = self.graph.mercator.pt_to_mercator(Coord { x: lon, y: lat });
+        let i = self
+            .snap_to_crossing_junction(pt.into())
+            .map_err(err_to_js)?;
+        self.set_crossing(i, kind).map_err(err_to_js)
+    }
+
+    #[wasm_bindgen(js_name = getCrossings)]
+    pub fn get_crossings_wasm(&self) -> Result<Vec<u8>, JsValue> {
+        serde_json::to_vec(&self.get_crossings()).map_err(err_to_js)
+    }
+
+    #[wasm_bindgen(js_name = listScenarios)]
+    pub fn list_scenarios_wasm(&self) -> Vec<String> {
+        self.list_scenarios()
+    }
+
+    #[wasm_bindgen(js_name = getCurrentScenario)]
+    pub fn get_current_scenario_wasm(&self) -> String {
+        self.get_current_scenario().to_string()
+    }
+
+    #[wasm_bindgen(js_name = switchScenario)]
+    pub fn switch_scenario_wasm(&mut self, name: String) -> Result<(), JsValue> {
+        self.switch_scenario(&name).map_err(err_to_js)
+    }
+
+    #[wasm_bindgen(js_name = cloneScenario)]
+    pub fn clone_scenario_wasm(&mut self, new_name: String) -> Result<(), JsValue> {
+        self.clone_scenario(&new_name).map_err(err_to_js)
+    }
+
+    #[wasm_bindgen(js_name = renameScenario)]
+    pub fn rename_scenario_wasm(&mut self, name: String, new_name: String) -> Result<(), JsValue> {
+        self.rename_scenario(&name, &new_name).map_err(err_to_js)
+    }
+
+    #[wasm_bindgen(js_name = deleteScenario)]
+    pub fn delete_scenario_wasm(&mut self, name: String) -> Result<(), JsValue> {
+        self.delete_scenario(&name).map_err(err_to_js)
+    }
+
+    /// Returns JSON with stats and network lengths per scenario
+    #[wasm_bindgen(js_name = compareScenarios)]
+    pub fn compare_scenarios_wasm(&mut self) -> Result<String, JsValue> {
+        self.compare_scenarios().map_err(err_to_js)
+    }
+
     #[wasm_bindgen(js_name = clearAllRoutes)]
     pub fn clear_all_routes_wasm(&mut self) {
         self.clear_all_routes()
@@ -243,6 +308,20 @@ impl MapModel {
         self.get_route_sections(ids).map_err(err_to_js)
     }
 
+    /// Returns a JSONified list of movements through major junctions along one route
+    #[wasm_bindgen(js_name = getRouteJunctions)]
+    pub fn get_route_junctions_wasm(&self, id: usize) -> Result<String, JsValue> {
+        let movements = self.assess_route_junctions(id).map_err(err_to_js)?;
+        serde_json::to_string(&movements).map_err(err_to_js)
+    }
+
+    /// Returns GJ points for every movement through a major junction along any route
+    #[wasm_bindgen(js_name = getJunctionAssessment)]
+    pub fn get_junction_assessment_wasm(&self) -> Result<Vec<u8>, JsValue> {
+        let gj = self.get_junction_assessment().map_err(err_to_js)?;
+        serde_json::to_vec(&gj).map_err(err_to_js)
+    }
+
     #[wasm_bindgen(js_name = evaluateRoute)]
     pub fn evaluate_route_wasm(&mut self, input: JsValue) -> Result<String, JsValue> {
         if !self.quiet_router_ok {
@@ -269,10 +348,108 @@ impl MapModel {
                     return Err(err_to_js(format!("evaluateRoute got bad breakdown {x}")));
                 }
             },
+            req.rider,
         )
         .map_err(err_to_js)
     }
 
+    /// Takes the same input as `evaluateRoute`, ignoring `breakdown`. Compares quiet routes on the
+    /// baseline network and the current one.
+    #[wasm_bindgen(js_name = evaluateRouteBeforeAfter)]
+    pub fn evaluate_route_before_after_wasm(&mut self, input: JsValue) -> Result<String, JsValue> {
+        let req: EvaluateRouteRequest = serde_wasm_bindgen::from_value(input)?;
+        let mut timer = Timer::new("evaluate route before and after", None);
+        let result = self
+            .evaluate_route_before_after(
+                self.graph.mercator.pt_to_mercator(Coord {
+                    x: req.x1,
+                    y: req.y1,
+                }),
+                self.graph.mercator.pt_to_mercator(Coord {
+                    x: req.x2,
+                    y: req.y2,
+                }),
+                &mut timer,
+            )
+            .map_err(err_to_js);
+        timer.done();
+        result
+    }
+
+    /// Takes the same input as `evaluateRoute`, ignoring `breakdown`. Returns up to `k` quiet
+    /// routes, discarding any more than `max_stretch` times longer than the best.
+    #[wasm_bindgen(js_name = evaluateRouteAlternatives)]
+    pub fn evaluate_route_alternatives_wasm(
+        &mut self,
+        input: JsValue,
+        k: usize,
+        max_stretch: f64,
+    ) -> Result<String, JsValue> {
+        let req: EvaluateRouteRequest = serde_wasm_bindgen::from_value(input)?;
+        let mut timer = Timer::new("evaluate route alternatives", None);
+        let result = self
+            .evaluate_route_alternatives(
+                self.graph.mercator.pt_to_mercator(Coord {
+                    x: req.x1,
+                    y: req.y1,
+                }),
+                self.graph.mercator.pt_to_mercator(Coord {
+                    x: req.x2,
+                    y: req.y2,
+                }),
+                k,
+                max_stretch,
+                &mut timer,
+            )
+            .map_err(err_to_js);
+        timer.done();
+        result
+    }
+
+    /// Takes a list of [lon, lat] waypoints and evaluates the journey through all of them
+    #[wasm_bindgen(js_name = evaluateMultiPointRoute)]
+    pub fn evaluate_multi_point_route_wasm(&mut self, input: JsValue) -> Result<String, JsValue> {
+        if !self.quiet_router_ok {
+            let mut timer = Timer::new("recalculate bicycle_quiet", None);
+            self.recalculate_quiet_router(&mut timer);
+        }
+
+        let points: Vec<[f64; 2]> = serde_wasm_bindgen::from_value(input)?;
+        let waypoints: Vec<Coord> = points
+            .into_iter()
+            .map(|[x, y]| self.graph.mercator.pt_to_mercator(Coord { x, y }))
+            .collect();
+        self.evaluate_multi_point_route(&waypoints)
+            .map_err(err_to_js)
+    }
+
+    /// Takes CSV text in the format described by `parse_journeys_csv` and returns a JSON list of
+    /// `JourneyMetrics`
+    #[wasm_bindgen(js_name = evaluateJourneysCsv)]
+    pub fn evaluate_journeys_csv_wasm(&mut self, csv: String) -> Result<String, JsValue> {
+        let journeys = parse_journeys_csv(&csv).map_err(err_to_js)?;
+        let mut timer = Timer::new("evaluate journeys", None);
+        let metrics = self.evaluate_journeys(journeys, &mut timer);
+        timer.done();
+        serde_json::to_string(&metrics).map_err(err_to_js)
+    }
+
+    /// Returns everywhere reachable by bike from a point within exactly one of a distance
+    /// (meters) or time (minutes) limit
+    #[wasm_bindgen(js_name = getIsochrone)]
+    pub fn get_isochrone_wasm(
+        &self,
+        lon: f64,
+        lat: f64,
+        max_distance_meters: Option<f64>,
+        max_minutes: Option<f64>,
+        only_high_los: bool,
+    ) -> Result<String, JsValue> {
+        let pt = self.graph.mercator.pt_to_mercator(Coord { x: lon, y: lat });
+        self.get_isochrone(pt.into(), max_distance_meters, max_minutes, only_high_los)
+            .map_err(err_to_js)
+    }
+
     #[wasm_bindgen(js_name = debugReachablePath)]
     pub fn debug_reachable_path_wasm(&self, kind: &str, idx: usize) -> Result<String, JsValue> {
         let roads = self.get_poi_roads(kind, idx)?;
@@ -292,6 +469,68 @@ impl MapModel {
         self.fix_unreachable_poi(roads).map_err(err_to_js)
     }
 
+    /// Proposes connections for every unreachable POI of the given kinds (a JSON array), sharing
+    /// roads where possible. If `apply` is true, also creates all of the routes.
+    #[wasm_bindgen(js_name = fixAllUnreachablePOIs)]
+    pub fn fix_all_unreachable_pois_wasm(
+        &mut self,
+        kinds: String,
+        apply: bool,
+    ) -> Result<String, JsValue> {
+        let kinds: Vec<String> = serde_json::from_str(&kinds).map_err(err_to_js)?;
+        self.fix_all_unreachable_pois(kinds, apply)
+            .map_err(err_to_js)
+    }
+
+    /// Returns a JSON list of routes ranked by how much `metric` would worsen per km without them
+    #[wasm_bindgen(js_name = rankRouteContributions)]
+    pub fn rank_route_contributions_wasm(
+        &mut self,
+        metric: String,
+        include_od: bool,
+    ) -> Result<String, JsValue> {
+        let mut timer = Timer::new("rank route contributions", None);
+        let results = self
+            .rank_route_contributions(&metric, include_od, &mut timer)
+            .map_err(err_to_js);
+        timer.done();
+        serde_json::to_string(&results?).map_err(err_to_js)
+    }
+
+    /// Recommends infrastructure types per route within a budget. `objective` is a JSON
+    /// `Objective`. Returns JSON, without changing anything.
+    #[wasm_bindgen(js_name = optimiseInfraTypes)]
+    pub fn optimise_infra_types_wasm(
+        &mut self,
+        budget: f64,
+        objective: String,
+    ) -> Result<String, JsValue> {
+        let objective: Objective = serde_json::from_str(&objective).map_err(err_to_js)?;
+        if objective == Objective::OdHighLos && !self.quiet_router_ok {
+            let mut timer = Timer::new("recalculate quiet router", None);
+            self.recalculate_quiet_router(&mut timer);
+            timer.done();
+        }
+        let result = self
+            .optimise_infra_types(budget, objective)
+            .map_err(err_to_js)?;
+        serde_json::to_string(&result).map_err(err_to_js)
+    }
+
+    /// Returns a JSON report about one route
+    #[wasm_bindgen(js_name = getRouteReport)]
+    pub fn get_route_report_wasm(&mut self, id: usize) -> Result<String, JsValue> {
+        let report = self.get_route_report(id).map_err(err_to_js)?;
+        serde_json::to_string(&report).map_err(err_to_js)
+    }
+
+    /// Returns GJ with the severance roads and junctions that would make the most population and
+    /// POIs reachable if fixed individually
+    #[wasm_bindgen(js_name = rankSeverances)]
+    pub fn rank_severances_wasm(&self, limit: usize) -> Result<Vec<u8>, JsValue> {
+        self.rank_severances(limit).map_err(err_to_js)
+    }
+
     #[wasm_bindgen(js_name = evaluateOD)]
     pub fn evaluate_od_wasm(&mut self, fast_sample: bool) -> Result<Vec<u8>, JsValue> {
         if !self.quiet_router_ok {
@@ -332,6 +571,34 @@ impl MapModel {
         serde_json::to_string(&props).map_err(err_to_js)
     }
 
+    /// Returns JSON with coherence scores for the whole network (`network`), the same for the
+    /// baseline (`baseline`), and per route (`routes`, as GJ). Network-wide directness is slow
+    /// and only included when `include_directness` is set.
+    #[wasm_bindgen(js_name = getCoherenceScorecard)]
+    pub fn get_coherence_scorecard_wasm(
+        &mut self,
+        include_directness: bool,
+    ) -> Result<String, JsValue> {
+        let slow_stats = if include_directness {
+            let mut timer = Timer::new("calculate directness", None);
+            if !self.quiet_router_ok {
+                self.recalculate_quiet_router(&mut timer);
+            }
+            let slow_stats = self.get_slow_stats(&mut timer);
+            timer.done();
+            Some(slow_stats)
+        } else {
+            None
+        };
+
+        serde_json::to_string(&serde_json::json!({
+            "network": self.get_coherence_scores(slow_stats.as_ref()),
+            "baseline": self.get_baseline_coherence(),
+            "routes": self.get_route_coherence_scores().map_err(err_to_js)?,
+        }))
+        .map_err(err_to_js)
+    }
+
     #[wasm_bindgen(js_name = recalculateODStats)]
     pub fn recalculate_od_stats_wasm(&mut self) -> Result<String, JsValue> {
         let mut timer = Timer::new("recalculate OD stats", None);
@@ -341,53 +608,40 @@ impl MapModel {
     }
 
     #[wasm_bindgen(js_name = loadSavefile)]
-    pub fn load_savefile(&mut self, input: String) -> Result<(), JsValue> {
-        let savefile: FeatureCollection = serde_json::from_str(&input).map_err(err_to_js)?;
-        let Some(foreign_members) = savefile.foreign_members.as_ref() else {
-            return Err(JsValue::from_str(
-                "GeoJSON is missing foreign members section",
-            ));
-        };
+    pub fn load_savefile_wasm(&mut self, input: String) -> Result<(), JsValue> {
+        self.load_savefile(&input).map_err(err_to_js)
+    }
 
-        let mut ok = false;
-        if let Some(Value::Number(num)) = foreign_members.get("version") {
-            if let Some(version) = num.as_u64() {
-                ok = version == 2;
-            }
-        }
-        if !ok {
-            return Err(JsValue::from_str("Savefile is out-of-date"));
-        }
+    /// Replaces all KPI targets, from a JSONified list
+    #[wasm_bindgen(js_name = setTargets)]
+    pub fn set_targets_wasm(&mut self, input: String) -> Result<(), JsValue> {
+        let targets: Vec<Target> = serde_json::from_str(&input).map_err(err_to_js)?;
+        self.set_targets(targets).map_err(err_to_js)
+    }
 
-        let Some(Value::String(study_area_name)) = foreign_members.get("study_area_name") else {
-            return Err(JsValue::from_str("Savefile is mising study_area_name"));
-        };
-        if study_area_name != &self.study_area_name {
-            return Err(JsValue::from_str(&format!(
-                "Savefile is for {study_area_name}, but you are currently in {}",
-                self.study_area_name
-            )));
-        }
+    #[wasm_bindgen(js_name = getTargets)]
+    pub fn get_targets_wasm(&self) -> Result<String, JsValue> {
+        serde_json::to_string(self.get_targets()).map_err(err_to_js)
+    }
 
-        self.id_counter = match foreign_members.get("id_counter") {
-            Some(Value::Number(num)) => match num.as_u64() {
-                Some(num) => num as usize,
-                None => {
-                    return Err(JsValue::from_str("Savefile has bad id_counter"));
-                }
-            },
-            _ => {
-                return Err(JsValue::from_str("Savefile is missing id_counter"));
-            }
+    /// Returns a JSONified compliance scorecard, with one entry per target. Slow stats are only
+    /// calculated if some target needs them.
+    #[wasm_bindgen(js_name = evaluateTargets)]
+    pub fn evaluate_targets_wasm(&mut self) -> Result<String, JsValue> {
+        let slow_stats = if self.targets_need_slow_stats() {
+            let mut timer = Timer::new("recalculate slow stats for targets", None);
+            self.recalculate_quiet_router(&mut timer);
+            let slow_stats = self.get_slow_stats(&mut timer);
+            timer.done();
+            Some(slow_stats)
+        } else {
+            None
         };
 
-        for feature in savefile.features {
-            let route: SavedRoute = geojson::de::from_feature(feature).map_err(err_to_js)?;
-            self.routes.insert(route.id, route.to_in_memory(self));
-        }
-
-        self.recalculate_after_edits();
-        Ok(())
+        let results = self
+            .evaluate_targets(slow_stats.as_ref())
+            .map_err(err_to_js)?;
+        serde_json::to_string(&results).map_err(err_to_js)
     }
 
     #[wasm_bindgen(js_name = getGridMeshDensity)]
@@ -412,6 +666,18 @@ impl MapModel {
         self.import_arterial_roads();
     }
 
+    /// Creates routes covering the most demand and POIs within a length (meters) and/or cost
+    /// (pounds) budget. Returns a summary of what was added.
+    #[wasm_bindgen(js_name = generateDraftNetwork)]
+    pub fn generate_draft_network_wasm(
+        &mut self,
+        max_length_meters: Option<f64>,
+        max_cost: Option<f64>,
+    ) -> Result<String, JsValue> {
+        self.generate_draft_network(max_length_meters, max_cost)
+            .map_err(err_to_js)
+    }
+
     // TODO Except greenspaces
     #[wasm_bindgen(js_name = getPOIs)]
     pub fn get_pois(&self) -> Result<Vec<u8>, JsValue> {
@@ -420,13 +686,19 @@ impl MapModel {
 
         let mut features = Vec::new();
         for (idx, poi) in self.schools.iter().enumerate() {
-            features.push(poi.to_gj(&self.graph.mercator, roads.covers(poi.road), idx));
+            let mut f = poi.to_gj(&self.graph.mercator, roads.covers(poi.road), idx);
+            f.set_property("network_distance", roads.distance(poi.road));
+            features.push(f);
         }
         for (idx, poi) in self.gp_hospitals.iter().enumerate() {
-            features.push(poi.to_gj(&self.graph.mercator, roads.covers(poi.road), idx));
+            let mut f = poi.to_gj(&self.graph.mercator, roads.covers(poi.road), idx);
+            f.set_property("network_distance", roads.distance(poi.road));
+            features.push(f);
         }
         for (idx, poi) in self.railway_stations.iter().enumerate() {
-            features.push(poi.to_gj(&self.graph.mercator, roads.covers(poi.road), idx));
+            let mut f = poi.to_gj(&self.graph.mercator, roads.covers(poi.road), idx);
+            f.set_property("network_distance", roads.distance(poi.road));
+            features.push(f);
         }
 
         serde_json::to_vec(&FeatureCollection {
@@ -437,6 +709,16 @@ impl MapModel {
         .map_err(err_to_js)
     }
 
+    /// Only roads within this many meters of the network count as reachable. Pass undefined to
+    /// remove the limit.
+    #[wasm_bindgen(js_name = setReachabilityLimit)]
+    pub fn set_reachability_limit_wasm(
+        &mut self,
+        limit_meters: Option<f64>,
+    ) -> Result<(), JsValue> {
+        self.set_reachability_limit(limit_meters).map_err(err_to_js)
+    }
+
     #[wasm_bindgen(js_name = getGreenspaces)]
     pub fn get_greenspaces(&self) -> Result<Vec<u8>, JsValue> {
         // TODO Some kind of caching would make this nicer
@@ -449,7 +731,13 @@ impl MapModel {
                 .greenspaces
                 .iter()
                 .enumerate()
-                .flat_map(|(idx, x)| x.to_gj(&self.graph.mercator, roads.covers_any(&x.roads), idx))
+                .flat_map(|(idx, x)| {
+                    let mut features =
+                        x.to_gj(&self.graph.mercator, roads.covers_any(&x.roads), idx);
+                    // Only on the polygon, not the access points
+                    features[0].set_property("network_distance", roads.min_distance(&x.roads));
+                    features
+                })
                 .collect(),
         })
         .map_err(err_to_js)
@@ -467,7 +755,11 @@ impl MapModel {
                 .town_centres
                 .iter()
                 .enumerate()
-                .map(|(idx, x)| x.to_gj(&self.graph.mercator, roads.covers_any(&x.roads), idx))
+                .map(|(idx, x)| {
+                    let mut f = x.to_gj(&self.graph.mercator, roads.covers_any(&x.roads), idx);
+                    f.set_property("network_distance", roads.min_distance(&x.roads));
+                    f
+                })
                 .collect(),
         })
         .map_err(err_to_js)
@@ -503,7 +795,11 @@ impl MapModel {
                 .settlements
                 .iter()
                 .enumerate()
-                .map(|(idx, x)| x.to_gj(&self.graph.mercator, roads.covers_any(&x.roads), idx))
+                .map(|(idx, x)| {
+                    let mut f = x.to_gj(&self.graph.mercator, roads.covers_any(&x.roads), idx);
+                    f.set_property("network_distance", roads.min_distance(&x.roads));
+                    f
+                })
                 .collect(),
         })
         .map_err(err_to_js)
@@ -550,11 +846,38 @@ impl MapModel {
         .map_err(err_to_js)
     }
 
+    /// Returns GJ with suggested routes to close short gaps between dead-ends of the network and
+    /// other parts of the network or unreachable POIs
+    #[wasm_bindgen(js_name = findNetworkGaps)]
+    pub fn find_network_gaps_wasm(&self, max_distance_meters: f64) -> Result<String, JsValue> {
+        self.find_network_gaps(max_distance_meters)
+            .map_err(err_to_js)
+    }
+
+    /// Returns GJ with the corridors of high and medium demand roads with the most demand not yet
+    /// served by high LoS routes
+    #[wasm_bindgen(js_name = getUnmetDemand)]
+    pub fn get_unmet_demand_wasm(&self, limit: usize) -> Result<String, JsValue> {
+        self.get_unmet_demand(limit).map_err(err_to_js)
+    }
+
     #[wasm_bindgen(js_name = getConnectedComponents)]
     pub fn get_connected_components_wasm(&self) -> Result<String, JsValue> {
         serde_json::to_string(&self.get_connected_components()).map_err(err_to_js)
     }
 
+    /// Proposes routes to join disconnected parts of the network, preferring either the shortest
+    /// links or ones with the best level of service
+    #[wasm_bindgen(js_name = suggestComponentLinks)]
+    pub fn suggest_component_links_wasm(
+        &self,
+        best_los: bool,
+        limit: usize,
+    ) -> Result<String, JsValue> {
+        self.suggest_component_links(best_los, limit)
+            .map_err(err_to_js)
+    }
+
     /// From exactly two waypoints, return a list of extra intermediate nodes and a boolean to
     /// indicate if they're snappable or not.
     #[wasm_bindgen(js_name = getExtraNodes)]
@@ -598,6 +921,25 @@ impl MapModel {
         self.get_town_centre_routes().map_err(err_to_js)
     }
 
+    /// Returns candidate long-distance corridors between settlements, as GJ with SetRouteInput
+    /// properties
+    #[wasm_bindgen(js_name = generateLongDistanceNetwork)]
+    pub fn generate_long_distance_network_wasm(
+        &mut self,
+        max_distance_meters: f64,
+    ) -> Result<String, JsValue> {
+        let mut timer = Timer::new("recalculate quiet router", None);
+        if !self.quiet_router_ok {
+            self.recalculate_quiet_router(&mut timer);
+        }
+
+        let result = self
+            .generate_long_distance_network(max_distance_meters)
+            .map_err(err_to_js);
+        timer.done();
+        result
+    }
+
     #[wasm_bindgen(js_name = getNetworkLengths)]
     pub fn get_network_lengths_wasm(&self) -> Result<String, JsValue> {
         self.get_network_lengths().map_err(err_to_js)
@@ -610,17 +952,11 @@ impl MapModel {
     }
 
     fn get_poi_roads(&self, kind: &str, idx: usize) -> Result<HashSet<RoadID>, JsValue> {
-        match kind {
-            "schools" => Ok([self.schools[idx].road].into()),
-            "gp_hospitals" => Ok([self.gp_hospitals[idx].road].into()),
-            "railway_stations" => Ok([self.railway_stations[idx].road].into()),
-            "greenspaces" => Ok(self.greenspaces[idx].roads.clone()),
-            "town_centres" => Ok(self.town_centres[idx].roads.clone()),
-            "settlements" => Ok(self.settlements[idx].roads.clone()),
-            _ => Err(err_to_js(format!(
-                "debug_reachable_path_wasm got bad kind {kind}"
-            ))),
+        let mut pois = self.get_pois_of_kind(kind).map_err(err_to_js)?;
+        if idx >= pois.len() {
+            return Err(err_to_js(format!("No {kind} {idx}")));
         }
+        Ok(pois.swap_remove(idx))
     }
 }
 
@@ -630,7 +966,10 @@ struct EvaluateRouteRequest {
     y1: f64,
     x2: f64,
     y2: f64,
+    #[serde(default)]
     breakdown: String,
+    #[serde(default)]
+    rider: RiderType,
 }
 
 fn err_to_js<E: std::fmt::Display>(err: E) -> JsValue {
@@ -3,7 +3,7 @@
 AREA=$1
 set -ex
 
-cargo run --release -- \
+cargo run --release -- build \
   --country england \
   --input "../data_prep/england/osm/out/$AREA.osm.pbf" \
   --boundary "../data_prep/england/osm/$AREA.geojson" \
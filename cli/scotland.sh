@@ -3,7 +3,7 @@
 AREA=$1
 set -ex
 
-cargo run --release -- \
+cargo run --release -- build \
   --country scotland \
   --input "../data_prep/scotland/osm/out/$AREA.osm.pbf" \
   --boundary "../data_prep/scotland/osm/$AREA.geojson" \
@@ -0,0 +1,17 @@
+use std::io::BufReader;
+
+use anyhow::Result;
+use backend::MapModel;
+use fs_err::File;
+
+/// Loads a map model built by this tool, then applies a savefile from the web UI to it
+pub fn load_model(model_path: &str, savefile_path: &str) -> Result<MapModel> {
+    info!("Loading {model_path}");
+    let reader = BufReader::new(File::open(model_path)?);
+    let mut model: MapModel = bincode::deserialize_from(reader)?;
+    model.recalculate_after_edits();
+
+    info!("Loading {savefile_path}");
+    model.load_savefile(&fs_err::read_to_string(savefile_path)?)?;
+    Ok(model)
+}
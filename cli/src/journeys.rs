@@ -0,0 +1,50 @@
+use anyhow::Result;
+use backend::journeys::parse_journeys_csv;
+use fs_err::File;
+use graph::Timer;
+
+use crate::headless::load_model;
+
+pub fn run(
+    model_path: &str,
+    savefile_path: &str,
+    csv_path: &str,
+    output: Option<String>,
+) -> Result<()> {
+    let mut model = load_model(model_path, savefile_path)?;
+    let journeys = parse_journeys_csv(&fs_err::read_to_string(csv_path)?)?;
+
+    let mut timer = Timer::new("evaluate journeys", None);
+    let metrics = model.evaluate_journeys(journeys, &mut timer);
+    timer.done();
+
+    println!(
+        "{:<20} {:>12} {:>12} {:>12} {:>12} {:>12}",
+        "Journey", "Direct (m)", "Quiet (m)", "Quiet (min)", "% High LoS", "Max grad %"
+    );
+    for m in &metrics {
+        if let Some(ref err) = m.error {
+            println!("{:<20} failed: {err}", m.id);
+            continue;
+        }
+        println!(
+            "{:<20} {:>12.0} {:>12.0} {:>12.1} {:>12.1} {:>12.1}",
+            m.id,
+            m.direct_length,
+            m.quiet_length,
+            m.quiet_minutes,
+            m.percent_high_los * 100.0,
+            m.max_gradient
+        );
+    }
+
+    if let Some(path) = output {
+        info!("Writing {path}");
+        let mut writer = csv::Writer::from_writer(File::create(path)?);
+        for m in &metrics {
+            writer.serialize(m)?;
+        }
+        writer.flush()?;
+    }
+    Ok(())
+}
@@ -6,64 +6,187 @@ extern crate log;
 use std::io::BufWriter;
 
 use anyhow::Result;
-use clap::Parser;
+use clap::{Parser, Subcommand};
 use fs_err::File;
 use graph::Timer;
 
 mod common;
 mod disconnected;
 mod england;
+mod headless;
+mod journeys;
+mod report;
+mod scorecard;
 mod scotland;
+mod sensitivity;
 
 #[derive(Parser)]
 struct Args {
-    #[arg(long)]
-    country: String,
+    #[command(subcommand)]
+    command: Command,
+}
 
-    /// Path to a .osm.pbf or .xml file to convert
-    #[arg(long)]
-    input: String,
+#[derive(Subcommand)]
+enum Command {
+    /// Build a map model for one study area
+    Build {
+        #[arg(long)]
+        country: String,
 
-    /// Path to GeoJSON file with the boundary to clip the input to
-    #[arg(long)]
-    boundary: String,
+        /// Path to a .osm.pbf or .xml file to convert
+        #[arg(long)]
+        input: String,
 
-    /// Map model output file to write
-    #[arg(long)]
-    output: String,
+        /// Path to GeoJSON file with the boundary to clip the input to
+        #[arg(long)]
+        boundary: String,
 
-    /// Baseline stats output file to write
-    #[arg(long)]
-    stats_output: String,
+        /// Map model output file to write
+        #[arg(long)]
+        output: String,
+
+        /// Baseline stats output file to write
+        #[arg(long)]
+        stats_output: String,
+    },
+
+    /// Print a compliance scorecard for the KPI targets in a savefile
+    Scorecard {
+        /// Path to a map model .bin file, not gzipped
+        #[arg(long)]
+        model: String,
+
+        /// Path to a savefile exported from the web UI
+        #[arg(long)]
+        savefile: String,
+    },
+
+    /// Print a JSON assessment report for one route in a savefile
+    Report {
+        /// Path to a map model .bin file, not gzipped
+        #[arg(long)]
+        model: String,
+
+        /// Path to a savefile exported from the web UI
+        #[arg(long)]
+        savefile: String,
+
+        /// The route ID
+        #[arg(long)]
+        id: usize,
+
+        /// Also write the report as a standalone HTML file here
+        #[arg(long)]
+        html: Option<String>,
+    },
+
+    /// Report how much KPIs change across a grid of LoS penalties and demand thresholds
+    Sensitivity {
+        /// Path to a map model .bin file, not gzipped
+        #[arg(long)]
+        model: String,
+
+        /// Path to a savefile exported from the web UI
+        #[arg(long)]
+        savefile: String,
+
+        /// Comma-separated multipliers for how far each LoS penalty is above 1
+        #[arg(long, value_delimiter = ',', default_values_t = vec![0.5, 1.0, 2.0])]
+        penalty_scales: Vec<f64>,
+
+        /// Comma-separated multipliers for the high and medium demand thresholds
+        #[arg(long, value_delimiter = ',', default_values_t = vec![0.5, 1.0, 1.5])]
+        threshold_scales: Vec<f64>,
+
+        /// Also write every run as JSON here
+        #[arg(long)]
+        output: Option<String>,
+    },
+
+    /// Evaluate a CSV of journeys, with x1, y1, x2, y2 columns in WGS84 and an optional id
+    Journeys {
+        /// Path to a map model .bin file, not gzipped
+        #[arg(long)]
+        model: String,
+
+        /// Path to a savefile exported from the web UI
+        #[arg(long)]
+        savefile: String,
+
+        /// Path to the CSV of journeys
+        #[arg(long)]
+        csv: String,
+
+        /// Also write metrics per journey as CSV here
+        #[arg(long)]
+        output: Option<String>,
+    },
 }
 
 fn main() -> Result<()> {
     simple_logger::init_with_level(log::Level::Info).unwrap();
     let args = Args::parse();
 
+    match args.command {
+        Command::Build {
+            country,
+            input,
+            boundary,
+            output,
+            stats_output,
+        } => build(country, input, boundary, output, stats_output),
+        Command::Scorecard { model, savefile } => scorecard::run(&model, &savefile),
+        Command::Report {
+            model,
+            savefile,
+            id,
+            html,
+        } => report::run(&model, &savefile, id, html),
+        Command::Sensitivity {
+            model,
+            savefile,
+            penalty_scales,
+            threshold_scales,
+            output,
+        } => sensitivity::run(&model, &savefile, penalty_scales, threshold_scales, output),
+        Command::Journeys {
+            model,
+            savefile,
+            csv,
+            output,
+        } => journeys::run(&model, &savefile, &csv, output),
+    }
+}
+
+fn build(
+    country: String,
+    input: String,
+    boundary: String,
+    output: String,
+    stats_output: String,
+) -> Result<()> {
     let mut timer = Timer::new("build model", None);
-    let osm_bytes = fs_err::read(&args.input)?;
-    let boundary_gj = fs_err::read_to_string(&args.boundary)?;
-    let study_area_name = args
-        .output
+    let osm_bytes = fs_err::read(&input)?;
+    let boundary_gj = fs_err::read_to_string(&boundary)?;
+    let study_area_name = output
         .split("/")
         .last()
         .unwrap()
         .strip_suffix(".bin")
         .unwrap()
         .to_string();
-    let model = match args.country.as_ref() {
+    let model = match country.as_ref() {
         "scotland" => scotland::create(study_area_name, &osm_bytes, &boundary_gj, &mut timer)?,
         "england" => england::create(study_area_name, &osm_bytes, &boundary_gj, &mut timer)?,
         x => bail!("Unknown country {x}"),
     };
 
     timer.step("writing");
-    let writer = BufWriter::new(File::create(&args.output)?);
+    let writer = BufWriter::new(File::create(&output)?);
     bincode::serialize_into(writer, &model)?;
 
     fs_err::write(
-        &args.stats_output,
+        &stats_output,
         serde_json::to_string(&model.get_baseline_stats())?,
     )?;
 
@@ -0,0 +1,157 @@
+use std::collections::BTreeMap;
+
+use anyhow::Result;
+use backend::RouteReport;
+
+use crate::headless::load_model;
+
+pub fn run(model_path: &str, savefile_path: &str, id: usize, html: Option<String>) -> Result<()> {
+    let mut model = load_model(model_path, savefile_path)?;
+    let report = model.get_route_report(id)?;
+    println!("{}", serde_json::to_string_pretty(&report)?);
+
+    if let Some(path) = html {
+        info!("Writing {path}");
+        fs_err::write(path, to_html(&report)?)?;
+    }
+    Ok(())
+}
+
+/// A standalone page, with no external scripts or styles
+fn to_html(report: &RouteReport) -> Result<String> {
+    let mut html = String::new();
+    html.push_str("<!DOCTYPE html>\n<html>\n<head>\n<meta charset=\"utf-8\">\n");
+    html.push_str(&format!(
+        "<title>Route report: {}</title>\n",
+        escape(&report.name)
+    ));
+    html.push_str(
+        "<style>body { font-family: sans-serif; max-width: 50em; margin: auto; } \
+         table { border-collapse: collapse; margin-bottom: 1em; } \
+         td, th { border: 1px solid #ccc; padding: 4px 8px; text-align: left; }</style>\n",
+    );
+    html.push_str("</head>\n<body>\n");
+
+    html.push_str(&format!(
+        "<h1>{} (route {})</h1>\n",
+        escape(&report.name),
+        report.id
+    ));
+    if !report.notes.is_empty() {
+        html.push_str(&format!("<p>{}</p>\n", escape(&report.notes)));
+    }
+
+    html.push_str("<h2>Summary</h2>\n<table>\n");
+    for (key, value) in [
+        ("Tier", format!("{:?}", report.tier)),
+        ("Infrastructure type", format!("{:?}", report.infra_type)),
+        ("Length", format!("{:.0}m", report.length_meters)),
+        ("Total climb", format!("{:.1}m", report.total_climb_meters)),
+        ("Estimated cost", format!("£{:.0}", report.estimated_cost)),
+        ("Maximum demand", report.max_demand.to_string()),
+        (
+            "High demand length",
+            format!("{:.0}m", report.high_demand_length),
+        ),
+        (
+            "Medium demand length",
+            format!("{:.0}m", report.medium_demand_length),
+        ),
+    ] {
+        html.push_str(&format!("<tr><th>{key}</th><td>{value}</td></tr>\n"));
+    }
+    html.push_str("</table>\n");
+
+    length_table(
+        &mut html,
+        "Length by level of service",
+        &report.breakdown.length_by_los,
+    );
+    length_table(
+        &mut html,
+        "Length by infrastructure type",
+        &report.breakdown.length_by_infra_type,
+    );
+    length_table(
+        &mut html,
+        "Length by gradient",
+        &report.breakdown.length_by_gradient_group,
+    );
+
+    html.push_str("<h2>Newly reachable with this route</h2>\n<table>\n");
+    for (key, count) in &report.newly_reachable {
+        html.push_str(&format!(
+            "<tr><th>{}</th><td>{count}</td></tr>\n",
+            escape(key)
+        ));
+    }
+    html.push_str("</table>\n");
+
+    html.push_str("<h2>Elevation profile</h2>\n");
+    html.push_str(&elevation_svg(&report.elevation_profile));
+
+    html.push_str("<h2>Street space issues</h2>\n");
+    if report.street_space_issues.is_empty() {
+        html.push_str("<p>None</p>\n");
+    } else {
+        html.push_str("<table>\n<tr><th>Road</th><th>Name</th><th>Length</th></tr>\n");
+        for issue in &report.street_space_issues {
+            html.push_str(&format!(
+                "<tr><td>{}</td><td>{}</td><td>{:.0}m</td></tr>\n",
+                issue.road,
+                escape(issue.name.as_deref().unwrap_or("")),
+                issue.length_meters
+            ));
+        }
+        html.push_str("</table>\n");
+    }
+
+    html.push_str("<h2>Raw data</h2>\n<pre>");
+    html.push_str(&escape(&serde_json::to_string_pretty(report)?));
+    html.push_str("</pre>\n</body>\n</html>\n");
+    Ok(html)
+}
+
+fn length_table(html: &mut String, title: &str, lengths: &BTreeMap<String, f64>) {
+    html.push_str(&format!("<h2>{title}</h2>\n<table>\n"));
+    for (key, length) in lengths {
+        html.push_str(&format!(
+            "<tr><th>{}</th><td>{length:.0}m</td></tr>\n",
+            escape(key)
+        ));
+    }
+    html.push_str("</table>\n");
+}
+
+fn elevation_svg(profile: &[(f64, f64)]) -> String {
+    let (width, height) = (600.0, 150.0);
+    let total_distance = profile.last().map(|(d, _)| *d).unwrap_or(0.0);
+    let min_elevation = profile.iter().map(|(_, e)| *e).fold(f64::MAX, f64::min);
+    let max_elevation = profile.iter().map(|(_, e)| *e).fold(f64::MIN, f64::max);
+    let range = max_elevation - min_elevation;
+    // Only for scaling, so flat or empty routes don't divide by zero
+    let (x_scale, y_scale) = (total_distance.max(1.0), range.max(1.0));
+
+    let points: Vec<String> = profile
+        .iter()
+        .map(|(d, e)| {
+            let x = d / x_scale * width;
+            let y = height - (e - min_elevation) / y_scale * height;
+            format!("{x:.1},{y:.1}")
+        })
+        .collect();
+    format!(
+        "<svg width=\"{width}\" height=\"{height}\" style=\"border: 1px solid #ccc\">\
+         <polyline fill=\"none\" stroke=\"black\" points=\"{}\"/></svg>\n\
+         <p>{:.1}m between the lowest and highest points over {total_distance:.0}m</p>\n",
+        points.join(" "),
+        range
+    )
+}
+
+fn escape(x: &str) -> String {
+    x.replace('&', "&amp;")
+        .replace('<', "&lt;")
+        .replace('>', "&gt;")
+        .replace('"', "&quot;")
+}
@@ -0,0 +1,62 @@
+use anyhow::Result;
+use backend::targets::{Comparison, TargetResult};
+use graph::Timer;
+
+use crate::headless::load_model;
+
+pub fn run(model_path: &str, savefile_path: &str) -> Result<()> {
+    let mut model = load_model(model_path, savefile_path)?;
+    if model.get_targets().is_empty() {
+        bail!("{savefile_path} doesn't have any targets defined");
+    }
+
+    let slow_stats = if model.targets_need_slow_stats() {
+        let mut timer = Timer::new("calculate slow stats", None);
+        model.recalculate_quiet_router(&mut timer);
+        let slow_stats = model.get_slow_stats(&mut timer);
+        timer.done();
+        Some(slow_stats)
+    } else {
+        None
+    };
+
+    let results = model.evaluate_targets(slow_stats.as_ref())?;
+    println!(
+        "{:<40} {:>12} {:>12} {:>12} {:>12} {:<10} {}",
+        "Target", "Goal", "Current", "Gap", "Baseline", "Trend", "Result"
+    );
+    for result in &results {
+        print_row(result);
+    }
+
+    let passed = results.iter().filter(|r| r.pass).count();
+    println!("\n{passed} of {} targets met", results.len());
+    Ok(())
+}
+
+fn print_row(result: &TargetResult) {
+    let goal = format!(
+        "{} {}",
+        match result.comparison {
+            Comparison::AtLeast => ">=",
+            Comparison::AtMost => "<=",
+        },
+        fmt_number(Some(result.target))
+    );
+    let trend = format!("{:?}", result.trend);
+    println!(
+        "{:<40} {goal:>12} {:>12} {:>12} {:>12} {trend:<10} {}",
+        result.name,
+        fmt_number(result.current),
+        fmt_number(result.gap),
+        fmt_number(result.baseline),
+        if result.pass { "PASS" } else { "FAIL" }
+    );
+}
+
+fn fmt_number(x: Option<f64>) -> String {
+    match x {
+        Some(x) => format!("{x:.3}"),
+        None => "-".to_string(),
+    }
+}
@@ -0,0 +1,39 @@
+use anyhow::Result;
+use graph::Timer;
+
+use crate::headless::load_model;
+
+pub fn run(
+    model_path: &str,
+    savefile_path: &str,
+    penalty_scales: Vec<f64>,
+    threshold_scales: Vec<f64>,
+    output: Option<String>,
+) -> Result<()> {
+    let mut model = load_model(model_path, savefile_path)?;
+
+    let mut timer = Timer::new("sensitivity analysis", None);
+    let report = model.run_sensitivity(&penalty_scales, &threshold_scales, &mut timer)?;
+    timer.done();
+
+    println!(
+        "{:<45} {:>12} {:>12} {:>12} {:>12}",
+        "KPI", "Default", "Min", "Max", "Spread"
+    );
+    for (kpi, spread) in &report.summary {
+        let reference = match spread.reference {
+            Some(x) => format!("{x:.3}"),
+            None => "-".to_string(),
+        };
+        println!(
+            "{kpi:<45} {reference:>12} {:>12.3} {:>12.3} {:>12.3}",
+            spread.min, spread.max, spread.spread
+        );
+    }
+
+    if let Some(path) = output {
+        info!("Writing {path}");
+        fs_err::write(path, serde_json::to_string_pretty(&report)?)?;
+    }
+    Ok(())
+}
@@ -7,7 +7,8 @@ type Subpage =
   | "mesh-density"
   | "streetspace"
   | "calculated-routes"
-  | "directness-network";
+  | "directness-network"
+  | "scorecard";
 export let subpage: Writable<Subpage> = writable("overview");
 
 export let showDirectness: Writable<"direct" | "quiet"> = writable("quiet");
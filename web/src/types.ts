@@ -40,6 +40,7 @@ export interface RouteGJ extends FeatureCollection {
   direct_bike_length: number;
   straight_line_length: number;
   directions: Step[];
+  instructions: Instruction[];
 }
 
 export interface Step {
@@ -50,6 +51,18 @@ export interface Step {
   los: string;
 }
 
+export interface Instruction {
+  name?: string;
+  length: number;
+  way: string;
+  infra_type: InfraType;
+  los: string;
+  turn: string;
+  turn_angle: number;
+  crosses_non_high_los: boolean;
+  leaves_network: boolean;
+}
+
 export type EvaluateODOut = FeatureCollection<
   LineString,
   { count: number; infra_type: InfraType; los: string }
@@ -105,6 +118,17 @@ export interface SlowStats {
   worst_directness_routes: WorstRoutes;
 }
 
+export interface TargetResult {
+  name: string;
+  comparison: "AtLeast" | "AtMost";
+  target: number;
+  current: number | null;
+  baseline: number | null;
+  pass: boolean;
+  gap: number | null;
+  trend: "Improved" | "Worsened" | "Unchanged" | "Unknown";
+}
+
 export type WorstRoutes = [
   { x: number; y: number },
   { x: number; y: number },
@@ -18,6 +18,7 @@ import type {
   SetRouteInput,
   SlowStats,
   Stats,
+  TargetResult,
   Tier,
   TownCentreRoutes,
   Waypoint,
@@ -180,6 +181,11 @@ export class InnerBackend {
     return JSON.parse(this.inner!.getBaselineStats());
   }
 
+  evaluateTargets(): TargetResult[] {
+    this.checkReady();
+    return JSON.parse(this.inner!.evaluateTargets());
+  }
+
   recalculateODStats(): ODStats {
     this.checkReady();
     return JSON.parse(this.inner!.recalculateODStats());